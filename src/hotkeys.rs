@@ -0,0 +1,265 @@
+use rdev::{EventType, Key, listen};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HotkeyAction {
+    MarkStart,
+    MarkEnd,
+    CreateSnippet,
+    InsertSelected,
+    CycleSnippet,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 5] = [
+        HotkeyAction::MarkStart,
+        HotkeyAction::MarkEnd,
+        HotkeyAction::CreateSnippet,
+        HotkeyAction::InsertSelected,
+        HotkeyAction::CycleSnippet,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HotkeyAction::MarkStart => "MarkStart",
+            HotkeyAction::MarkEnd => "MarkEnd",
+            HotkeyAction::CreateSnippet => "CreateSnippet",
+            HotkeyAction::InsertSelected => "InsertSelected",
+            HotkeyAction::CycleSnippet => "CycleSnippet",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HotkeyAction::MarkStart => "Mark start",
+            HotkeyAction::MarkEnd => "Mark end",
+            HotkeyAction::CreateSnippet => "Create snippet",
+            HotkeyAction::InsertSelected => "Insert selected snippet",
+            HotkeyAction::CycleSnippet => "Cycle snippet",
+        }
+    }
+}
+
+// Touches qu'on accepte dans le fichier de config, par leur nom rdev
+const KEYS: &[Key] = &[
+    Key::KeyA, Key::KeyB, Key::KeyC, Key::KeyD, Key::KeyE, Key::KeyF, Key::KeyG,
+    Key::KeyH, Key::KeyI, Key::KeyJ, Key::KeyK, Key::KeyL, Key::KeyM, Key::KeyN,
+    Key::KeyO, Key::KeyP, Key::KeyQ, Key::KeyR, Key::KeyS, Key::KeyT, Key::KeyU,
+    Key::KeyV, Key::KeyW, Key::KeyX, Key::KeyY, Key::KeyZ,
+    Key::Num0, Key::Num1, Key::Num2, Key::Num3, Key::Num4,
+    Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6,
+    Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    Key::Kp0, Key::Kp1, Key::Kp2, Key::Kp3, Key::Kp4,
+    Key::Kp5, Key::Kp6, Key::Kp7, Key::Kp8, Key::Kp9,
+    Key::UpArrow, Key::DownArrow, Key::LeftArrow, Key::RightArrow,
+    Key::Home, Key::End, Key::PageUp, Key::PageDown, Key::Insert, Key::Delete,
+    Key::Space, Key::Tab, Key::Return, Key::Escape, Key::Backspace,
+    Key::Minus, Key::Equal, Key::LeftBracket, Key::RightBracket,
+    Key::SemiColon, Key::Quote, Key::BackQuote, Key::BackSlash,
+    Key::Comma, Key::Dot, Key::Slash,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hotkey {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub key: Key,
+}
+
+impl Hotkey {
+    pub fn new(ctrl: bool, shift: bool, alt: bool, key: Key) -> Self {
+        Self { ctrl, shift, alt, key }
+    }
+
+    /// Parse une combinaison du style `Ctrl+Shift+KeyS`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut hotkey = Self::new(false, false, false, Key::Unknown(0));
+        let mut has_key = false;
+        for part in text.split('+').map(|p| p.trim()) {
+            match part {
+                "Ctrl" => hotkey.ctrl = true,
+                "Shift" => hotkey.shift = true,
+                "Alt" => hotkey.alt = true,
+                name => {
+                    hotkey.key = *KEYS.iter().find(|k| format!("{:?}", k) == name)?;
+                    has_key = true;
+                }
+            }
+        }
+        has_key.then_some(hotkey)
+    }
+}
+
+impl std::fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if self.ctrl {
+            parts.push("Ctrl".to_string());
+        }
+        if self.shift {
+            parts.push("Shift".to_string());
+        }
+        if self.alt {
+            parts.push("Alt".to_string());
+        }
+        parts.push(format!("{:?}", self.key));
+        write!(f, "{}", parts.join("+"))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HotkeyBindings {
+    pub bindings: Vec<(HotkeyAction, Hotkey)>,
+}
+
+impl HotkeyBindings {
+    pub fn new() -> Self {
+        Self {
+            bindings: vec![
+                (HotkeyAction::MarkStart, Hotkey::new(true, true, false, Key::Num1)),
+                (HotkeyAction::MarkEnd, Hotkey::new(true, true, false, Key::Num2)),
+                (HotkeyAction::CreateSnippet, Hotkey::new(true, true, false, Key::Num3)),
+                (HotkeyAction::InsertSelected, Hotkey::new(true, true, false, Key::Num4)),
+                (HotkeyAction::CycleSnippet, Hotkey::new(true, true, false, Key::Num5)),
+            ],
+        }
+    }
+
+    pub fn get(&self, action: HotkeyAction) -> Option<Hotkey> {
+        self.bindings.iter().find(|(a, _)| *a == action).map(|(_, h)| *h)
+    }
+
+    pub fn set(&mut self, action: HotkeyAction, hotkey: Hotkey) {
+        if let Some(binding) = self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            binding.1 = hotkey;
+        } else {
+            self.bindings.push((action, hotkey));
+        }
+    }
+}
+
+/// Modificateurs et touches enfoncés d'après les événements reçus.
+#[derive(Default)]
+struct KeyboardState {
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+    /// Touches non modificatrices encore enfoncées
+    held: Vec<Key>,
+}
+
+impl KeyboardState {
+    fn update_modifier(&mut self, key: Key, pressed: bool) -> bool {
+        match key {
+            Key::ControlLeft | Key::ControlRight => self.ctrl = pressed,
+            Key::ShiftLeft | Key::ShiftRight => self.shift = pressed,
+            Key::Alt | Key::AltGr => self.alt = pressed,
+            _ => return false,
+        }
+        true
+    }
+
+    /// true seulement au premier appui : la répétition automatique de l'OS renvoie des KeyPress
+    /// tant que la touche reste enfoncée, une insertion ne doit pas être refaite à chacun.
+    fn press(&mut self, key: Key) -> bool {
+        if self.update_modifier(key, true) || self.held.contains(&key) {
+            return false;
+        }
+        self.held.push(key);
+        true
+    }
+
+    fn release(&mut self, key: Key) {
+        if !self.update_modifier(key, false) {
+            self.held.retain(|k| *k != key);
+        }
+    }
+
+    fn matches(&self, hotkey: &Hotkey, key: Key) -> bool {
+        hotkey.key == key && hotkey.ctrl == self.ctrl && hotkey.shift == self.shift && hotkey.alt == self.alt
+    }
+}
+
+/// Lance l'écoute globale du clavier dans un thread à part.
+/// Les bindings sont partagés pour pouvoir être modifiés sans relancer le thread.
+/// `ctx` sert à réveiller l'UI : sans repaint, une action attendrait que la fenêtre
+/// reprenne le focus alors que les hotkeys servent justement depuis le jeu.
+pub fn spawn_listener(bindings: Arc<Mutex<HotkeyBindings>>, ctx: egui::Context) -> Receiver<HotkeyAction> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut keyboard = KeyboardState::default();
+        let result = listen(move |event| {
+            match event.event_type {
+                EventType::KeyPress(key) => {
+                    if !keyboard.press(key) {
+                        return;
+                    }
+                    let Ok(bindings) = bindings.lock() else {
+                        return;
+                    };
+                    for (action, hotkey) in &bindings.bindings {
+                        if keyboard.matches(hotkey, key) {
+                            let _ = sender.send(*action);
+                            ctx.request_repaint();
+                        }
+                    }
+                }
+                EventType::KeyRelease(key) => keyboard.release(key),
+                _ => {}
+            }
+        });
+        if let Err(e) = result {
+            println!("Hotkey listener stopped: {:?}", e);
+        }
+    });
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_repeat_fires_once() {
+        let mut keyboard = KeyboardState::default();
+        assert!(!keyboard.press(Key::ControlLeft));
+        assert!(!keyboard.press(Key::ShiftLeft));
+        assert!(keyboard.press(Key::Num4));
+        assert!(!keyboard.press(Key::Num4));
+        assert!(!keyboard.press(Key::Num4));
+        let insert = Hotkey::new(true, true, false, Key::Num4);
+        assert!(keyboard.matches(&insert, Key::Num4));
+
+        keyboard.release(Key::Num4);
+        assert!(keyboard.press(Key::Num4));
+    }
+
+    #[test]
+    fn modifiers_follow_releases() {
+        let mut keyboard = KeyboardState::default();
+        keyboard.press(Key::ControlRight);
+        keyboard.press(Key::Alt);
+        keyboard.release(Key::ControlRight);
+        assert!(!keyboard.ctrl);
+        assert!(keyboard.alt);
+        assert!(keyboard.held.is_empty());
+        assert!(!keyboard.matches(&Hotkey::new(true, false, true, Key::KeyS), Key::KeyS));
+        assert!(keyboard.matches(&Hotkey::new(false, false, true, Key::KeyS), Key::KeyS));
+    }
+
+    #[test]
+    fn hotkeys_round_trip_through_text() {
+        let hotkey = Hotkey::new(true, false, true, Key::KeyS);
+        assert_eq!(hotkey.to_string(), "Ctrl+Alt+KeyS");
+        assert_eq!(Hotkey::parse(&hotkey.to_string()), Some(hotkey));
+        assert_eq!(Hotkey::parse("Ctrl+Shift"), None);
+    }
+}
@@ -1,9 +1,11 @@
 mod utils;
 mod snippets;
 mod ui;
+mod source;
+mod hotkeys;
+mod settings;
 
-use rosu_memory_lib::init_loop;
-use std::sync::{Arc, Mutex};
+use source::GameSources;
 use ui::{AppState, render_side_panel, render_central_panel, render_right_panel, render_toast};
 
 impl eframe::App for AppState {
@@ -12,6 +14,11 @@ impl eframe::App for AppState {
             self.mania_renderer = Some(ui::mania::ManiaRenderer::new());
         }
 
+        self.handle_game_source();
+        self.handle_hotkeys();
+        self.handle_index_updates();
+        self.handle_auto_tag();
+
         render_toast(self, ctx);
         render_side_panel(self, ctx);
         render_central_panel(self, ctx);
@@ -22,9 +29,6 @@ impl eframe::App for AppState {
 }
 
 fn main() -> eyre::Result<()> {
-    let (mut state, process) = init_loop(500)?;
-    println!("Successfully initialized!");
-    
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1200.0, 800.0]),
@@ -36,7 +40,7 @@ fn main() -> eyre::Result<()> {
         options,
         Box::new(|cc| {
             egui_extras::install_image_loaders(&cc.egui_ctx);
-            Ok(Box::new(AppState::new(Arc::new(process), Arc::new(Mutex::new(state)))))
+            Ok(Box::new(AppState::new(GameSources::new(), cc.egui_ctx.clone())))
         }),
     ).unwrap();
     
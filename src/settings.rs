@@ -0,0 +1,83 @@
+use eyre::Result;
+use std::fs;
+use std::path::PathBuf;
+use crate::hotkeys::{Hotkey, HotkeyAction, HotkeyBindings};
+use crate::snippets::library::{LibraryKind, LibraryRoot};
+
+const SETTINGS_FILE: &str = "settings.ini";
+
+/// Réglages persistés à côté de l'exécutable, au format `[Section]` / `Clé: valeur`.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    pub hotkeys: HotkeyBindings,
+    /// Dossiers de snippets, le premier accessible en écriture est celui par défaut pour sauvegarder
+    pub libraries: Vec<LibraryRoot>,
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Self {
+            hotkeys: HotkeyBindings::new(),
+            libraries: vec![LibraryRoot::default_personal()],
+        }
+    }
+
+    pub fn path() -> PathBuf {
+        std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.join(SETTINGS_FILE)))
+            .unwrap_or_else(|| PathBuf::from(SETTINGS_FILE))
+    }
+
+    pub fn load() -> Self {
+        let mut settings = Self::new();
+        let Ok(content) = fs::read_to_string(Self::path()) else {
+            return settings;
+        };
+
+        let mut section = String::new();
+        let mut libraries = Vec::new();
+        for line in content.lines().map(|l| l.trim()) {
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line[1..line.len() - 1].to_string();
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            if section == "Hotkeys" {
+                match (HotkeyAction::from_name(key), Hotkey::parse(value)) {
+                    (Some(action), Some(hotkey)) => settings.hotkeys.set(action, hotkey),
+                    _ => println!("Ignoring invalid hotkey {key}: {value}"),
+                }
+            } else if section == "Libraries" {
+                // Une ligne par dossier, `kind: chemin`, la même clé peut revenir plusieurs fois
+                match LibraryKind::from_name(key) {
+                    Some(kind) if !value.is_empty() => libraries.push(LibraryRoot::new(PathBuf::from(value), kind)),
+                    _ => println!("Ignoring invalid library {key}: {value}"),
+                }
+            }
+        }
+        if !libraries.is_empty() {
+            settings.libraries = libraries;
+        }
+        settings
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let mut content = String::from("[Hotkeys]\n");
+        for (action, hotkey) in &self.hotkeys.bindings {
+            content.push_str(&format!("{}: {}\n", action.name(), hotkey));
+        }
+        content.push_str("\n[Libraries]\n");
+        for root in &self.libraries {
+            content.push_str(&format!("{}: {}\n", root.kind.name(), root.path.display()));
+        }
+        fs::write(Self::path(), content)?;
+        Ok(())
+    }
+}
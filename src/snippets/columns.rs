@@ -0,0 +1,53 @@
+use rosu_map::section::hit_objects::{HitObject, HitObjectKind};
+
+/// Colonne d'une note mania à partir de son x : `floor(x * keycount / 512)`.
+pub fn column_from_x(x: f32, keycount: usize) -> usize {
+    let keycount = keycount.max(1);
+    ((x * keycount as f32 / 512.0).floor().max(0.0) as usize).min(keycount - 1)
+}
+
+/// x écrit par l'éditeur d'osu! pour une colonne : le centre de la colonne, arrondi en dessous.
+pub fn column_x(column: usize, keycount: usize) -> f32 {
+    let keycount = keycount.max(1);
+    ((column as f32 + 0.5) * 512.0 / keycount as f32).floor()
+}
+
+pub fn column_of(hit_object: &HitObject, keycount: usize) -> Option<usize> {
+    match &hit_object.kind {
+        HitObjectKind::Circle(h) => Some(column_from_x(h.pos.x, keycount)),
+        HitObjectKind::Hold(h) => Some(column_from_x(h.pos_x, keycount)),
+        _ => None,
+    }
+}
+
+pub fn set_column(hit_object: &mut HitObject, column: usize, keycount: usize) {
+    let x = column_x(column, keycount);
+    match hit_object.kind {
+        HitObjectKind::Circle(ref mut h) => h.pos.x = x,
+        HitObjectKind::Hold(ref mut h) => h.pos_x = x,
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_x_round_trips_for_every_keycount() {
+        for keycount in 1..=18 {
+            for column in 0..keycount {
+                let x = column_x(column, keycount);
+                assert_eq!(column_from_x(x, keycount), column, "{}K column {} (x = {})", keycount, column, x);
+            }
+        }
+    }
+
+    #[test]
+    fn column_x_matches_editor_values() {
+        let xs_4k: Vec<f32> = (0..4).map(|c| column_x(c, 4)).collect();
+        assert_eq!(xs_4k, [64.0, 192.0, 320.0, 448.0]);
+        let xs_7k: Vec<f32> = (0..7).map(|c| column_x(c, 7)).collect();
+        assert_eq!(xs_7k, [36.0, 109.0, 182.0, 256.0, 329.0, 402.0, 475.0]);
+    }
+}
@@ -0,0 +1,167 @@
+use rosu_map::section::hit_objects::{HitObject, HitObjectKind};
+use crate::snippets::columns::column_of;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConflictKind {
+    /// Deux notes au même moment dans la même colonne
+    Overlap,
+    /// Une note tombe dans le body d'un LN de la même colonne
+    InsideHold,
+    /// Même colonne, trop proche pour être jouable
+    TooClose,
+}
+
+impl ConflictKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConflictKind::Overlap => "overlap",
+            ConflictKind::InsideHold => "inside LN",
+            ConflictKind::TooClose => "too close",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConflictMode {
+    Abort,
+    ReplaceRegion,
+    SkipConflicting,
+    FillEmpty,
+}
+
+impl ConflictMode {
+    pub const ALL: [ConflictMode; 4] = [
+        ConflictMode::Abort,
+        ConflictMode::ReplaceRegion,
+        ConflictMode::SkipConflicting,
+        ConflictMode::FillEmpty,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConflictMode::Abort => "Abort",
+            ConflictMode::ReplaceRegion => "Replace existing region",
+            ConflictMode::SkipConflicting => "Skip conflicting notes",
+            ConflictMode::FillEmpty => "Only fill empty columns",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    /// Index dans les notes à insérer
+    pub inserted: usize,
+    /// Index dans `beatmap.hit_objects`
+    pub existing: usize,
+    pub kind: ConflictKind,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ConflictReport {
+    pub conflicts: Vec<Conflict>,
+}
+
+impl ConflictReport {
+    pub fn is_empty(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    pub fn count(&self, kind: ConflictKind) -> usize {
+        self.conflicts.iter().filter(|c| c.kind == kind).count()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} overlaps, {} inside LNs, {} too close",
+            self.count(ConflictKind::Overlap),
+            self.count(ConflictKind::InsideHold),
+            self.count(ConflictKind::TooClose),
+        )
+    }
+}
+
+pub fn end_time(hit_object: &HitObject) -> f64 {
+    match &hit_object.kind {
+        HitObjectKind::Hold(h) => hit_object.start_time + h.duration,
+        _ => hit_object.start_time,
+    }
+}
+
+fn classify(new: &HitObject, existing: &HitObject, min_gap: f64) -> Option<ConflictKind> {
+    let (new_start, new_end) = (new.start_time, end_time(new));
+    let (old_start, old_end) = (existing.start_time, end_time(existing));
+
+    if (new_start - old_start).abs() < 1.0 {
+        return Some(ConflictKind::Overlap);
+    }
+    let inside_old = new_start > old_start && new_start <= old_end;
+    let inside_new = old_start > new_start && old_start <= new_end;
+    if inside_old || inside_new {
+        return Some(ConflictKind::InsideHold);
+    }
+    let gap = if new_start > old_end { new_start - old_end } else { old_start - new_end };
+    (gap < min_gap).then_some(ConflictKind::TooClose)
+}
+
+/// Compare chaque note à insérer aux notes déjà présentes dans la même colonne.
+pub fn analyse(existing: &[HitObject], inserted: &[HitObject], keycount: usize, min_gap: f64) -> ConflictReport {
+    let mut report = ConflictReport::default();
+    for (inserted_idx, new) in inserted.iter().enumerate() {
+        let Some(column) = column_of(new, keycount) else {
+            continue;
+        };
+        for (existing_idx, old) in existing.iter().enumerate() {
+            if column_of(old, keycount) != Some(column) {
+                continue;
+            }
+            if let Some(kind) = classify(new, old, min_gap) {
+                report.conflicts.push(Conflict {
+                    inserted: inserted_idx,
+                    existing: existing_idx,
+                    kind,
+                });
+            }
+        }
+    }
+    report
+}
+
+/// Applique le mode choisi : modifie les notes existantes et/ou celles à insérer.
+pub fn resolve(
+    existing: &mut Vec<HitObject>,
+    inserted: &mut Vec<HitObject>,
+    report: &ConflictReport,
+    mode: ConflictMode,
+    keycount: usize,
+    region: (f64, f64),
+) -> eyre::Result<()> {
+    match mode {
+        ConflictMode::Abort => {
+            if !report.is_empty() {
+                return Err(eyre::eyre!("Insert aborted: {}", report.summary()));
+            }
+        }
+        ConflictMode::ReplaceRegion => {
+            let (start, end) = region;
+            existing.retain(|h| end_time(h) < start || h.start_time > end);
+        }
+        ConflictMode::SkipConflicting => {
+            let mut idx = 0;
+            inserted.retain(|_| {
+                let keep = !report.conflicts.iter().any(|c| c.inserted == idx);
+                idx += 1;
+                keep
+            });
+        }
+        ConflictMode::FillEmpty => {
+            let (start, end) = region;
+            let used_columns: Vec<usize> = existing
+                .iter()
+                .filter(|h| end_time(h) >= start && h.start_time <= end)
+                .filter_map(|h| column_of(h, keycount))
+                .collect();
+            inserted.retain(|h| column_of(h, keycount).is_some_and(|c| !used_columns.contains(&c)));
+        }
+    }
+    Ok(())
+}
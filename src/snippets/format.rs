@@ -0,0 +1,353 @@
+use eyre::Result;
+use rosu_map::{Beatmap, DecodeBeatmap};
+use rosu_map::section::general::GameMode;
+use crate::snippets::columns;
+use crate::snippets::history;
+use crate::snippets::structs::{SnippetSource, Snippets};
+use crate::snippets::transform::ColumnTransform;
+use std::fs;
+use std::path::Path;
+use crate::utils;
+
+/// Première ligne des fichiers .snippets, le numéro permet de migrer plus tard.
+pub const HEADER: &str = "rosu-snippets format v1";
+const LEGACY_HEADER: &str = "osu file format";
+
+/// Les anciens .snippets étaient des .osu complets.
+pub fn is_legacy(text: &str) -> bool {
+    text.trim_start_matches('\u{feff}').trim_start().starts_with(LEGACY_HEADER)
+}
+
+fn is_section_header(line: &str) -> bool {
+    line.len() > 2 && line.starts_with('[') && line.ends_with(']') && line[1..line.len() - 1].chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Lignes d'une section, jusqu'au prochain `[Section]`.
+fn section<'a>(text: &'a str, name: &str) -> Vec<&'a str> {
+    let header = format!("[{}]", name);
+    text.lines()
+        .skip_while(|l| l.trim_end() != header)
+        .skip(1)
+        .take_while(|l| !is_section_header(l.trim_end()))
+        .collect()
+}
+
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+/// Préfixe des lignes de `[Notes]` : une note comme `[HitObjects]` ne doit pas passer pour une section.
+const NOTE_PREFIX: char = '|';
+
+/// Écrit la snippet dans le format texte v1.
+/// Les control points passent par l'encodeur de rosu-map pour garder la syntaxe des .osu.
+pub fn write(snippet: &Snippets) -> Result<String> {
+    let mut out = String::new();
+    out.push_str(HEADER);
+    out.push_str("\n\n[Metadata]\n");
+    out.push_str(&format!("Name: {}\n", single_line(&snippet.name)));
+    out.push_str(&format!("Tags: {}\n", single_line(&snippet.tags.join(" "))));
+    out.push_str(&format!("Author: {}\n", single_line(&snippet.author)));
+    out.push_str(&format!("SourceArtist: {}\n", single_line(&snippet.source.artist)));
+    out.push_str(&format!("SourceTitle: {}\n", single_line(&snippet.source.title)));
+    out.push_str(&format!("SourceVersion: {}\n", single_line(&snippet.source.version)));
+    out.push_str(&format!("SourceCreator: {}\n", single_line(&snippet.source.creator)));
+    out.push_str(&format!("SourceBeatmapID: {}\n", snippet.source.beatmap_id));
+    out.push_str(&format!("SourceBeatmapSetID: {}\n", snippet.source.beatmapset_id));
+    out.push_str(&format!("SourceStart: {}\n", snippet.source.start));
+    out.push_str(&format!("SourceEnd: {}\n", snippet.source.end));
+    out.push_str(&format!("SourceBPM: {}\n", snippet.source.bpm));
+    out.push_str(&format!("KeyCount: {}\n", snippet.keycount));
+    out.push_str(&format!("Mode: {}\n", GameMode::Mania as i32));
+    out.push_str(&format!("Created: {}\n", snippet.created_at));
+    out.push_str(&format!("Modified: {}\n", snippet.modified_at));
+    let seed = snippet.shuffle_seed.map(|s| s.to_string()).unwrap_or_default();
+    out.push_str(&format!("ShuffleSeed: {}\n", seed));
+    let transforms: Vec<String> = snippet.transforms.iter().map(|t| t.to_string()).collect();
+    out.push_str(&format!("Transforms: {}\n", transforms.join(" ")));
+
+    out.push_str("\n[Notes]\n");
+    for line in snippet.notes.lines() {
+        out.push(NOTE_PREFIX);
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    out.push_str("\n[TimingPoints]\n");
+    let mut map = Beatmap::default();
+    map.control_points.timing_points = snippet.timing_points.clone();
+    map.control_points.difficulty_points = snippet.difficulty_points.clone();
+    map.control_points.sample_points = snippet.sample_points.clone();
+    let mut encoded = Vec::new();
+    map.encode(&mut encoded)?;
+    let encoded = String::from_utf8(encoded)?;
+    for line in section(&encoded, "TimingPoints").into_iter().filter(|l| !l.trim().is_empty()) {
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    out.push_str("\n[HitObjects]\n");
+    for hit_object in &snippet.hit_objects {
+        out.push_str(&utils::hit_object_to_string(hit_object));
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Lit un fichier v1. Points et notes sont décodés par rosu-map via un .osu minimal.
+pub fn read(text: &str) -> Result<Snippets> {
+    let version = text.lines().next().unwrap_or_default().trim_start_matches('\u{feff}').trim();
+    if version != HEADER {
+        return Err(eyre::eyre!("Unsupported snippets format: {}", version));
+    }
+
+    let mut snippet = Snippets::new();
+    for line in section(text, "Metadata") {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "Name" => snippet.name = value.to_string(),
+            "Tags" => snippet.tags = value.split_whitespace().map(str::to_string).collect(),
+            "Author" => snippet.author = value.to_string(),
+            "SourceArtist" => snippet.source.artist = value.to_string(),
+            "SourceTitle" => snippet.source.title = value.to_string(),
+            "SourceVersion" => snippet.source.version = value.to_string(),
+            "SourceCreator" => snippet.source.creator = value.to_string(),
+            "SourceBeatmapID" => snippet.source.beatmap_id = value.parse().unwrap_or_default(),
+            "SourceBeatmapSetID" => snippet.source.beatmapset_id = value.parse().unwrap_or_default(),
+            "SourceStart" => snippet.source.start = value.parse().unwrap_or_default(),
+            "SourceEnd" => snippet.source.end = value.parse().unwrap_or_default(),
+            "SourceBPM" => snippet.source.bpm = value.parse().unwrap_or_default(),
+            "KeyCount" => snippet.keycount = value.parse()?,
+            "Mode" if value != (GameMode::Mania as i32).to_string() => {
+                return Err(eyre::eyre!("Only osu!mania snippets are supported, got mode {}", value));
+            }
+            "Created" => snippet.created_at = value.parse().unwrap_or_default(),
+            "Modified" => snippet.modified_at = value.parse().unwrap_or_default(),
+            "ShuffleSeed" => snippet.shuffle_seed = value.parse().ok(),
+            "Transforms" => {
+                snippet.transforms = value
+                    .split_whitespace()
+                    .map(|t| ColumnTransform::parse(t).ok_or_else(|| eyre::eyre!("Invalid transform: {}", t)))
+                    .collect::<Result<_>>()?;
+            }
+            _ => {}
+        }
+    }
+    let notes = section(text, "Notes");
+    let notes_len = notes.iter().rposition(|l| !l.trim().is_empty()).map(|i| i + 1).unwrap_or(0);
+    // Les fichiers écrits avant le préfixe ont leurs notes telles quelles
+    let notes: Vec<&str> = notes[..notes_len].iter().map(|l| l.strip_prefix(NOTE_PREFIX).unwrap_or(l)).collect();
+    snippet.notes = notes.join("\n");
+
+    let osu = format!(
+        "osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nCircleSize:{}\n\n[TimingPoints]\n{}\n\n[HitObjects]\n{}\n",
+        snippet.keycount,
+        section(text, "TimingPoints").join("\n"),
+        section(text, "HitObjects").join("\n"),
+    );
+    let beatmap = Beatmap::decode(osu.as_bytes())?;
+    snippet.hit_objects = beatmap.hit_objects;
+    snippet.timing_points = beatmap.control_points.timing_points;
+    snippet.difficulty_points = beatmap.control_points.difficulty_points;
+    snippet.sample_points = beatmap.control_points.sample_points;
+    snippet.is_saved = true;
+    Ok(snippet)
+}
+
+/// Métadonnées d'un fichier v1 pour l'index de la bibliothèque.
+#[derive(Clone, Debug)]
+pub struct Summary {
+    pub tags: Vec<String>,
+    pub keycount: usize,
+    pub object_count: usize,
+    pub bpm: f64,
+    pub duration: f64,
+    pub has_holds: bool,
+    /// `SnippetSource::label()` de la map d'origine, vide si inconnue
+    pub source: String,
+}
+
+/// Comme `read` mais sans décoder les notes : on lit juste les champs utiles ligne par ligne.
+pub fn read_summary(text: &str) -> Result<Summary> {
+    let version = text.lines().next().unwrap_or_default().trim_start_matches('\u{feff}').trim();
+    if version != HEADER {
+        return Err(eyre::eyre!("Unsupported snippets format: {}", version));
+    }
+
+    let mut summary = Summary {
+        tags: Vec::new(),
+        keycount: 4,
+        object_count: 0,
+        bpm: 0.0,
+        duration: 0.0,
+        has_holds: false,
+        source: String::new(),
+    };
+    let mut source = SnippetSource::new();
+    for line in section(text, "Metadata") {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "Tags" => summary.tags = value.split_whitespace().map(str::to_string).collect(),
+            "KeyCount" => summary.keycount = value.parse()?,
+            "SourceArtist" => source.artist = value.to_string(),
+            "SourceTitle" => source.title = value.to_string(),
+            "SourceVersion" => source.version = value.to_string(),
+            "SourceCreator" => source.creator = value.to_string(),
+            _ => {}
+        }
+    }
+    if source.is_known() {
+        summary.source = source.label();
+    }
+
+    // time,beatLength,meter,sampleSet,sampleIndex,volume,uninherited,effects
+    let beat_len = section(text, "TimingPoints")
+        .iter()
+        .map(|l| l.split(',').collect::<Vec<_>>())
+        .find(|f| f.get(6).is_none_or(|u| u.trim() == "1"))
+        .and_then(|f| f.get(1).and_then(|b| b.trim().parse::<f64>().ok()))
+        .filter(|b| *b > 0.0)
+        .unwrap_or(600.0);
+    summary.bpm = 60000.0 / beat_len;
+
+    // x,y,time,type,hitSound,endTime(:hitSample pour les LN)
+    for line in section(text, "HitObjects").iter().filter(|l| !l.trim().is_empty()) {
+        let fields: Vec<&str> = line.split(',').collect();
+        let start: f64 = fields.get(2).and_then(|t| t.parse().ok()).unwrap_or_default();
+        let kind: i32 = fields.get(3).and_then(|t| t.parse().ok()).unwrap_or_default();
+        let end = if kind & (1 << 7 | 1 << 3) != 0 {
+            fields.get(5).and_then(|f| f.split(':').next()).and_then(|t| t.parse().ok()).unwrap_or(start)
+        } else {
+            start
+        };
+        summary.has_holds |= kind & (1 << 7) != 0;
+        summary.object_count += 1;
+        summary.duration = summary.duration.max(end);
+    }
+    Ok(summary)
+}
+
+/// Lit un ancien .snippets (un .osu avec le nom dans Title et les tags dans Tags).
+/// Ils étaient écrits en `Mode: 0`, on force le mania pour que les LN soient bien lus.
+pub fn read_legacy(text: &str) -> Result<Snippets> {
+    let text: String = text
+        .lines()
+        .map(|l| if l.trim_start().starts_with("Mode:") { "Mode: 3" } else { l })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let beatmap = Beatmap::decode(text.as_bytes())?;
+
+    let mut snippet = Snippets::new();
+    snippet.name = beatmap.title.clone();
+    snippet.tags = beatmap.tags.split_whitespace().map(str::to_string).collect();
+    snippet.author = beatmap.creator.clone();
+    snippet.keycount = (beatmap.circle_size as usize).max(1);
+    snippet.hit_objects = beatmap.hit_objects;
+    snippet.timing_points = beatmap.control_points.timing_points;
+    snippet.difficulty_points = beatmap.control_points.difficulty_points;
+    snippet.sample_points = beatmap.control_points.sample_points;
+    for hit_object in snippet.hit_objects.iter_mut() {
+        if let Some(column) = columns::column_of(hit_object, snippet.keycount) {
+            columns::set_column(hit_object, column, snippet.keycount);
+        }
+    }
+    snippet.is_saved = true;
+    Ok(snippet)
+}
+
+/// Lit un .snippets quelle que soit sa version.
+pub fn read_any(text: &str) -> Result<Snippets> {
+    if is_legacy(text) {
+        read_legacy(text)
+    } else {
+        read(text)
+    }
+}
+
+/// Hash de ce qui fait le pattern (control points et objets), sans les métadonnées :
+/// la même snippet sauvegardée à un autre moment ou sous un autre nom garde le même hash.
+pub fn pattern_hash(text: &str) -> Result<u64> {
+    let normalized;
+    let text = if is_legacy(text) {
+        normalized = write(&read_legacy(text)?)?;
+        normalized.as_str()
+    } else {
+        text
+    };
+    let mut pattern = String::new();
+    for name in ["TimingPoints", "HitObjects"] {
+        for line in section(text, name).into_iter().map(str::trim).filter(|l| !l.is_empty()) {
+            pattern.push_str(line);
+            pattern.push('\n');
+        }
+    }
+    Ok(utils::content_hash(&pattern))
+}
+
+/// Réécrit un ancien .snippets au format v1, l'original est gardé dans `backups/`.
+/// Renvoie false si le fichier était déjà à jour.
+pub fn migrate_file(path: &Path) -> Result<bool> {
+    let text = fs::read_to_string(path)?;
+    if !is_legacy(&text) {
+        return Ok(false);
+    }
+    let snippet = read_legacy(&text)?;
+    let backups = history::backups_dir();
+    fs::create_dir_all(&backups)?;
+    // Le hash évite qu'un fichier du même nom dans un autre dossier écrase la sauvegarde
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("legacy.snippets");
+    let backup = format!("legacy_{:016x}_{}", utils::content_hash(&text), file_name);
+    fs::copy(path, backups.join(backup))?;
+    fs::write(path, write(&snippet)?)?;
+    println!("Migrated {} to {}", path.display(), HEADER);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet() -> Snippets {
+        let mut snippet = Snippets::from_hit_object_lines("64,192,0,1,0,0:0:0:0:\n448,192,250,128,0,500:0:0:0:0:", 4, 120.0).unwrap();
+        snippet.name = "test".to_string();
+        snippet
+    }
+
+    #[test]
+    fn notes_that_look_like_sections_round_trip() {
+        let mut snippet = snippet();
+        snippet.notes = "[Intro]\nfirst line\n\n[HitObjects]\n[TimingPoints]\n|pipe".to_string();
+        let text = write(&snippet).unwrap();
+        let reloaded = read(&text).unwrap();
+        assert_eq!(reloaded.notes, snippet.notes);
+        assert_eq!(reloaded.hit_objects.len(), 2);
+        assert_eq!(reloaded.timing_points.len(), 1);
+        assert_eq!(write(&reloaded).unwrap(), text);
+    }
+
+    #[test]
+    fn unprefixed_notes_are_read_as_is() {
+        let text = write(&snippet()).unwrap().replace("[Notes]\n", "[Notes]\nwritten before the prefix\n");
+        assert_eq!(read(&text).unwrap().notes, "written before the prefix");
+    }
+
+    #[test]
+    fn shuffle_seed_and_transforms_round_trip() {
+        let mut snippet = snippet();
+        snippet.shuffle_seed = Some(1234);
+        snippet.transforms = vec![ColumnTransform::Mirror, ColumnTransform::Rotate(2), ColumnTransform::NoJacks(99)];
+        let reloaded = read(&write(&snippet).unwrap()).unwrap();
+        assert_eq!(reloaded.shuffle_seed, Some(1234));
+        assert_eq!(reloaded.transforms, snippet.transforms);
+
+        let reloaded = read(&write(&Snippets::new()).unwrap()).unwrap();
+        assert_eq!(reloaded.shuffle_seed, None);
+        assert!(reloaded.transforms.is_empty());
+    }
+}
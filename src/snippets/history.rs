@@ -0,0 +1,309 @@
+use eyre::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::settings::Settings;
+use crate::snippets::transform::ColumnTransform;
+
+const BACKUPS_DIR: &str = "backups";
+const HISTORY_FILE: &str = "history.txt";
+/// Nom des entrées qui gardent le .osu tel qu'il était avant une restauration
+const RESTORE_PREFIX: &str = "Before restore of ";
+
+/// Dossier des sauvegardes, à côté des réglages et non dans le dossier courant.
+pub(crate) fn backups_dir() -> PathBuf {
+    Settings::path().with_file_name(BACKUPS_DIR)
+}
+
+/// Une insertion effectuée sur une beatmap, avec la copie du .osu d'origine.
+#[derive(Clone, Debug)]
+pub struct InsertRecord {
+    pub id: u128,
+    pub created_at: u64,
+    pub beatmap_path: PathBuf,
+    pub backup_path: PathBuf,
+    pub snippet_name: String,
+    pub placement_time: i32,
+    pub object_count: usize,
+    pub shuffle_seed: Option<u64>,
+    /// Transformations de colonnes de la snippet, seeds comprises, pour refaire la même insertion
+    pub transforms: Vec<ColumnTransform>,
+}
+
+impl InsertRecord {
+    fn to_line(&self) -> String {
+        let transforms: Vec<String> = self.transforms.iter().map(|t| t.to_string()).collect();
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.id,
+            self.created_at,
+            self.beatmap_path.display(),
+            self.backup_path.display(),
+            escape(&self.snippet_name),
+            self.placement_time,
+            self.object_count,
+            self.shuffle_seed.map(|s| s.to_string()).unwrap_or_default(),
+            transforms.join(" "),
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut parts = line.split('\t');
+        Some(Self {
+            id: parts.next()?.parse().ok()?,
+            created_at: parts.next()?.parse().ok()?,
+            beatmap_path: PathBuf::from(parts.next()?),
+            backup_path: PathBuf::from(parts.next()?),
+            snippet_name: unescape(parts.next()?),
+            placement_time: parts.next()?.parse().ok()?,
+            object_count: parts.next()?.parse().ok()?,
+            shuffle_seed: parts.next().and_then(|s| s.parse().ok()),
+            // Absent des lignes écrites avant qu'on garde les transformations
+            transforms: parts
+                .next()
+                .map(|t| t.split_whitespace().filter_map(ColumnTransform::parse).collect())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Le nom de la snippet est libre : tabulations et retours à la ligne casseraient la ligne.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Historique des insertions, persisté dans `backups/history.txt` à côté des réglages.
+#[derive(Clone, Debug)]
+pub struct InsertHistory {
+    pub records: Vec<InsertRecord>,
+    /// Dossier des sauvegardes et de `history.txt`
+    dir: PathBuf,
+}
+
+impl InsertHistory {
+    pub fn new() -> Self {
+        Self::in_dir(backups_dir())
+    }
+
+    /// Historique vide rangé dans `dir` plutôt qu'à côté des réglages.
+    pub(crate) fn in_dir(dir: PathBuf) -> Self {
+        Self { records: Vec::new(), dir }
+    }
+
+    pub fn load() -> Self {
+        let mut history = Self::new();
+        if let Ok(content) = fs::read_to_string(history.dir.join(HISTORY_FILE)) {
+            history.records = content.lines().filter_map(InsertRecord::from_line).collect();
+        }
+        history
+    }
+
+    fn save(&self) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let content: Vec<String> = self.records.iter().map(|r| r.to_line()).collect();
+        fs::write(self.dir.join(HISTORY_FILE), content.join("\n"))?;
+        Ok(())
+    }
+
+    /// Copie le .osu tel quel avant qu'il soit réécrit.
+    pub fn snapshot(&self, beatmap_path: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let mut id = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        let file_name = beatmap_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("beatmap.osu");
+        // Deux copies dans la même milliseconde ne doivent pas s'écraser
+        while self.dir.join(format!("{id}_{file_name}")).exists() {
+            id += 1;
+        }
+        let backup_path = self.dir.join(format!("{id}_{file_name}"));
+        fs::copy(beatmap_path, &backup_path)?;
+        Ok(backup_path)
+    }
+
+    /// Ajoute `record` à l'historique, son id et sa date sont ceux du moment de l'appel.
+    pub fn push(&mut self, mut record: InsertRecord) -> Result<InsertRecord> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        record.id = now.as_millis();
+        record.created_at = now.as_secs();
+        self.records.push(record.clone());
+        self.save()?;
+        Ok(record)
+    }
+
+    /// Remet le .osu dans l'état exact d'avant l'insertion `index`.
+    /// Le fichier actuel est d'abord copié dans sa propre entrée pour pouvoir annuler la restauration.
+    /// Les insertions suivantes sur la même map ne sont plus dans le fichier : elles quittent l'historique.
+    pub fn restore(&mut self, index: usize) -> Result<InsertRecord> {
+        let record = self.records.get(index).cloned().ok_or_else(|| eyre::eyre!("No insert at index {index}"))?;
+        let bytes = fs::read(&record.backup_path)?;
+        let current = self.snapshot(&record.beatmap_path)?;
+        if let Err(e) = fs::write(&record.beatmap_path, bytes) {
+            let _ = fs::remove_file(&current);
+            return Err(e.into());
+        }
+        println!("Restored {} from {}", record.beatmap_path.display(), record.backup_path.display());
+
+        let records = std::mem::take(&mut self.records);
+        for (idx, other) in records.into_iter().enumerate() {
+            if idx >= index && other.beatmap_path == record.beatmap_path {
+                let _ = fs::remove_file(&other.backup_path);
+            } else {
+                self.records.push(other);
+            }
+        }
+        self.push(InsertRecord {
+            id: 0,
+            created_at: 0,
+            beatmap_path: record.beatmap_path.clone(),
+            backup_path: current,
+            snippet_name: format!("{RESTORE_PREFIX}{}", record.snippet_name),
+            placement_time: record.placement_time,
+            object_count: 0,
+            shuffle_seed: None,
+            transforms: Vec::new(),
+        })?;
+        Ok(record)
+    }
+
+    /// Index de la dernière insertion faite sur `beatmap_path`.
+    fn last_for(&self, beatmap_path: &Path) -> Option<usize> {
+        self.records.iter().rposition(|r| r.beatmap_path == beatmap_path)
+    }
+
+    /// Annule la dernière insertion faite sur `beatmap_path`, les autres maps ne sont pas touchées.
+    /// Comme `restore`, l'état annulé reste récupérable depuis l'historique.
+    pub fn revert_last(&mut self, beatmap_path: &Path) -> Result<InsertRecord> {
+        let last = self
+            .last_for(beatmap_path)
+            .ok_or_else(|| eyre::eyre!("No insert to revert in {}", beatmap_path.display()))?;
+        self.restore(last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: u128, beatmap_path: &str) -> InsertRecord {
+        InsertRecord {
+            id,
+            created_at: 0,
+            beatmap_path: PathBuf::from(beatmap_path),
+            backup_path: PathBuf::from(format!("{id}.osu")),
+            snippet_name: format!("snippet {id}"),
+            placement_time: 1000,
+            object_count: 4,
+            shuffle_seed: Some(42),
+            transforms: vec![ColumnTransform::Mirror, ColumnTransform::NoJacks(7)],
+        }
+    }
+
+    #[test]
+    fn records_round_trip_through_lines() {
+        let original = record(7, "maps/a.osu");
+        let parsed = InsertRecord::from_line(&original.to_line()).unwrap();
+        assert_eq!(parsed.to_line(), original.to_line());
+        assert_eq!(parsed.transforms, original.transforms);
+    }
+
+    #[test]
+    fn snippet_names_with_tabs_round_trip() {
+        let mut original = record(7, "maps/a.osu");
+        original.snippet_name = "jumps\tfast\\slow\nend".to_string();
+        let line = original.to_line();
+        assert_eq!(line.split('\t').count(), 9);
+        let parsed = InsertRecord::from_line(&line).unwrap();
+        assert_eq!(parsed.snippet_name, original.snippet_name);
+        assert_eq!(parsed.placement_time, 1000);
+    }
+
+    /// Simule une insertion : sauvegarde puis réécriture du .osu.
+    fn insert(history: &mut InsertHistory, beatmap_path: &Path, name: &str, content: &str) {
+        let backup_path = history.snapshot(beatmap_path).unwrap();
+        fs::write(beatmap_path, content).unwrap();
+        history
+            .push(InsertRecord {
+                beatmap_path: beatmap_path.to_path_buf(),
+                backup_path,
+                snippet_name: name.to_string(),
+                ..record(0, "")
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn restore_backs_up_the_live_file_and_drops_later_inserts() {
+        let dir = std::env::temp_dir().join(format!("rosu-snippets-history-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let map = dir.join("a.osu");
+        let other = dir.join("b.osu");
+        fs::write(&map, "v0").unwrap();
+        fs::write(&other, "w0").unwrap();
+        let mut history = InsertHistory::in_dir(dir.join("backups"));
+        insert(&mut history, &map, "first", "v1");
+        insert(&mut history, &other, "other", "w1");
+        insert(&mut history, &map, "second", "v2");
+
+        let restored = history.restore(0).unwrap();
+        assert_eq!(restored.snippet_name, "first");
+        assert_eq!(fs::read_to_string(&map).unwrap(), "v0");
+        let names: Vec<&str> = history.records.iter().map(|r| r.snippet_name.as_str()).collect();
+        assert_eq!(names, ["other", "Before restore of first"]);
+        assert_eq!(fs::read_to_string(&history.records[1].backup_path).unwrap(), "v2");
+
+        // La restauration elle-même s'annule
+        history.revert_last(&map).unwrap();
+        assert_eq!(fs::read_to_string(&map).unwrap(), "v2");
+        assert_eq!(fs::read_to_string(&other).unwrap(), "w1");
+
+        history.revert_last(&other).unwrap();
+        assert_eq!(fs::read_to_string(&other).unwrap(), "w0");
+        assert_eq!(history.records.len(), 2);
+        // L'historique relu depuis le disque est le même
+        let saved = fs::read_to_string(dir.join("backups").join(HISTORY_FILE)).unwrap();
+        assert_eq!(saved.lines().filter_map(InsertRecord::from_line).count(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn lines_without_transforms_still_load() {
+        let parsed = InsertRecord::from_line("1\t0\tmaps/a.osu\t1.osu\tjs\t1000\t4\t42").unwrap();
+        assert_eq!(parsed.shuffle_seed, Some(42));
+        assert!(parsed.transforms.is_empty());
+    }
+
+    #[test]
+    fn last_insert_is_looked_up_per_map() {
+        let mut history = InsertHistory::new();
+        history.records = vec![record(1, "maps/a.osu"), record(2, "maps/b.osu"), record(3, "maps/a.osu"), record(4, "maps/b.osu")];
+        assert_eq!(history.last_for(Path::new("maps/a.osu")), Some(2));
+        assert_eq!(history.last_for(Path::new("maps/b.osu")), Some(3));
+        assert_eq!(history.last_for(Path::new("maps/c.osu")), None);
+    }
+
+    #[test]
+    fn backups_live_next_to_settings() {
+        assert_eq!(backups_dir().parent(), Settings::path().parent());
+    }
+}
@@ -0,0 +1,58 @@
+use rosu_map::section::hit_objects::HitObject;
+use rosu_map::section::hit_objects::hit_samples::{HitSampleDefaultName, HitSampleInfoName};
+
+/// Ce qu'on fait des hitsounds de la snippet à l'insertion.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HitsoundMode {
+    /// Samples de la snippet tels que capturés, avec ses points verts
+    Keep,
+    /// Plus aucune addition, les notes suivent les points de la map cible
+    Strip,
+    /// Garde les additions mais prend sample set, volume et index de la map cible
+    AdoptTarget,
+}
+
+impl HitsoundMode {
+    pub const ALL: [HitsoundMode; 3] = [
+        HitsoundMode::Keep,
+        HitsoundMode::Strip,
+        HitsoundMode::AdoptTarget,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HitsoundMode::Keep => "Keep snippet hitsounds",
+            HitsoundMode::Strip => "Strip hitsounds",
+            HitsoundMode::AdoptTarget => "Adopt target sample settings",
+        }
+    }
+}
+
+/// `Keep` ne touche à rien : un sample hérité (`0:0:0:0:`) le reste et suit les points verts
+/// de la snippet, copiés avec elle. Les autres modes remettent les samples à "hérité".
+pub fn apply(hit_objects: &mut [HitObject], mode: HitsoundMode) {
+    for hit_object in hit_objects.iter_mut() {
+        match mode {
+            HitsoundMode::Keep => {}
+            HitsoundMode::Strip => {
+                hit_object.samples.retain(|s| {
+                    matches!(s.name, HitSampleInfoName::Default(HitSampleDefaultName::Normal))
+                });
+                inherit(hit_object);
+            }
+            HitsoundMode::AdoptTarget => {
+                hit_object.samples.retain(|s| matches!(s.name, HitSampleInfoName::Default(_)));
+                inherit(hit_object);
+            }
+        }
+    }
+}
+
+/// Bank 0, index 0 et volume 0 : osu! utilise alors le point vert de la map.
+fn inherit(hit_object: &mut HitObject) {
+    for sample in hit_object.samples.iter_mut() {
+        sample.bank_specified = false;
+        sample.custom_sample_bank = 0;
+        sample.volume = 0;
+    }
+}
@@ -0,0 +1,199 @@
+use eyre::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::UNIX_EPOCH;
+use crate::settings::Settings;
+use crate::snippets::format;
+use crate::snippets::library::{self, LibraryFile, LibraryKind, LibraryRoot};
+use rosu_map::section::hit_objects::HitObjectKind;
+
+const INDEX_FILE: &str = "library_index.txt";
+/// Changé quand le sens d'une colonne change, un ancien index est alors reconstruit
+const INDEX_HEADER: &str = "rosu-snippets index v2";
+
+/// Ce que le side panel a besoin de savoir d'un fichier sans le décoder.
+#[derive(Clone, Debug)]
+pub struct IndexEntry {
+    pub path: PathBuf,
+    /// Index de la racine dans `settings.libraries`
+    pub root: usize,
+    pub category: String,
+    /// Nom du fichier, qui sert aussi de nom à la snippet
+    pub name: String,
+    pub tags: Vec<String>,
+    pub keycount: usize,
+    pub object_count: usize,
+    pub bpm: f64,
+    /// ms, fin des LN comprise
+    pub duration: f64,
+    pub has_holds: bool,
+    /// Artist - Title [Version] (Creator) de la map d'origine
+    pub source: String,
+    /// `format::pattern_hash`, pour reconnaître une snippet déjà présente
+    pub hash: u64,
+    /// Date de modification du fichier (ms), pour savoir s'il faut le relire
+    pub mtime: u64,
+}
+
+impl IndexEntry {
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{:016x}\t{}",
+            self.path.display(),
+            self.root,
+            self.category,
+            self.name,
+            self.tags.join(" "),
+            self.keycount,
+            self.object_count,
+            self.bpm,
+            self.duration,
+            self.has_holds,
+            self.source,
+            self.hash,
+            self.mtime,
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut parts = line.split('\t');
+        Some(Self {
+            path: PathBuf::from(parts.next()?),
+            root: parts.next()?.parse().ok()?,
+            category: parts.next()?.to_string(),
+            name: parts.next()?.to_string(),
+            tags: parts.next()?.split_whitespace().map(str::to_string).collect(),
+            keycount: parts.next()?.parse().ok()?,
+            object_count: parts.next()?.parse().ok()?,
+            bpm: parts.next()?.parse().ok()?,
+            duration: parts.next()?.parse().ok()?,
+            has_holds: parts.next()?.parse().ok()?,
+            source: parts.next()?.to_string(),
+            hash: u64::from_str_radix(parts.next()?, 16).ok()?,
+            mtime: parts.next()?.parse().ok()?,
+        })
+    }
+
+    /// Lit les métadonnées d'un fichier. Pour le format v1 on ne fait qu'un parcours
+    /// des lignes, seuls les anciens fichiers passent par le décodeur complet.
+    fn read(file: &LibraryFile, text: &str, mtime: u64) -> Result<Self> {
+        let summary = if format::is_legacy(text) {
+            let snippet = format::read_legacy(text)?;
+            format::Summary {
+                tags: snippet.tags.clone(),
+                keycount: snippet.keycount,
+                object_count: snippet.hit_objects.len(),
+                bpm: 60000.0 / snippet.base_beat_len(),
+                duration: snippet.duration(),
+                has_holds: snippet.hit_objects.iter().any(|h| matches!(h.kind, HitObjectKind::Hold(_))),
+                source: String::new(),
+            }
+        } else {
+            format::read_summary(text)?
+        };
+        Ok(Self {
+            path: file.path.clone(),
+            root: file.root,
+            category: file.category.clone(),
+            name: file.file_name(),
+            tags: summary.tags,
+            keycount: summary.keycount,
+            object_count: summary.object_count,
+            bpm: summary.bpm,
+            duration: summary.duration,
+            has_holds: summary.has_holds,
+            source: summary.source,
+            hash: format::pattern_hash(text)?,
+            mtime,
+        })
+    }
+}
+
+/// Index persistant de toutes les bibliothèques, pour ne pas décoder chaque fichier au démarrage.
+#[derive(Clone, Debug, Default)]
+pub struct LibraryIndex {
+    pub entries: Vec<IndexEntry>,
+}
+
+impl LibraryIndex {
+    fn path() -> PathBuf {
+        Settings::path().with_file_name(INDEX_FILE)
+    }
+
+    pub fn load() -> Self {
+        let mut index = Self::default();
+        if let Ok(content) = fs::read_to_string(Self::path()) {
+            let mut lines = content.lines();
+            if lines.next() == Some(INDEX_HEADER) {
+                index.entries = lines.filter_map(IndexEntry::from_line).collect();
+            }
+        }
+        index
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let mut content = vec![INDEX_HEADER.to_string()];
+        content.extend(self.entries.iter().map(|e| e.to_line()));
+        fs::write(Self::path(), content.join("\n"))?;
+        Ok(())
+    }
+
+    /// Rescanne les racines : les fichiers dont la date n'a pas changé reprennent leur entrée,
+    /// les autres sont relus. Les fichiers disparus sortent de l'index.
+    pub fn refresh(&self, roots: &[LibraryRoot]) -> Self {
+        let mut entries = Vec::new();
+        for file in library::scan(roots) {
+            let mtime = modified_time(&file.path);
+            let cached = self.entries.iter().find(|e| e.path == file.path && e.mtime == mtime);
+            if let Some(cached) = cached {
+                let mut entry = cached.clone();
+                entry.root = file.root;
+                entry.category = file.category.clone();
+                entries.push(entry);
+                continue;
+            }
+            // Les anciens .snippets (des .osu déguisés) sont convertis quand on les relit,
+            // seulement dans la bibliothèque perso : les fichiers partagés restent à leurs auteurs
+            let mut mtime = mtime;
+            if roots[file.root].kind == LibraryKind::Personal {
+                match format::migrate_file(&file.path) {
+                    Ok(true) => mtime = modified_time(&file.path),
+                    Ok(false) => {}
+                    Err(e) => println!("Failed to migrate {}: {}", file.path.display(), e),
+                }
+            }
+            let entry = fs::read_to_string(&file.path)
+                .map_err(eyre::Report::from)
+                .and_then(|text| IndexEntry::read(&file, &text, mtime));
+            match entry {
+                Ok(entry) => entries.push(entry),
+                Err(e) => println!("Failed to index {}: {}", file.path.display(), e),
+            }
+        }
+        Self { entries }
+    }
+}
+
+pub fn modified_time(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Rafraîchit l'index dans un thread, le résultat arrive par le channel une fois fini.
+pub fn spawn_refresh(index: LibraryIndex, roots: Vec<LibraryRoot>) -> Receiver<LibraryIndex> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let refreshed = index.refresh(&roots);
+        if let Err(e) = refreshed.save() {
+            println!("Failed to save library index: {}", e);
+        }
+        let _ = tx.send(refreshed);
+    });
+    rx
+}
@@ -0,0 +1,194 @@
+use rosu_map::section::hit_objects::{HitObject, HitObjectKind};
+use crate::snippets::columns;
+
+/// Comment placer les colonnes d'une snippet dans une map avec un autre keycount.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeycountStrategy {
+    /// Pattern centré, les colonnes qui dépassent sont perdues
+    Center,
+    /// Pattern collé à gauche
+    LeftAlign,
+    /// Colonnes réparties sur toute la largeur
+    Spread,
+    /// Pattern centré plus sa copie miroir
+    MirrorDuplicate,
+    /// Plusieurs colonnes fusionnées en une (pour réduire le keycount)
+    Merge,
+}
+
+impl KeycountStrategy {
+    pub const ALL: [KeycountStrategy; 5] = [
+        KeycountStrategy::Center,
+        KeycountStrategy::LeftAlign,
+        KeycountStrategy::Spread,
+        KeycountStrategy::MirrorDuplicate,
+        KeycountStrategy::Merge,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeycountStrategy::Center => "Center",
+            KeycountStrategy::LeftAlign => "Left align",
+            KeycountStrategy::Spread => "Spread",
+            KeycountStrategy::MirrorDuplicate => "Mirror duplicate",
+            KeycountStrategy::Merge => "Merge columns",
+        }
+    }
+}
+
+fn center(column: usize, from: usize, to: usize) -> Option<usize> {
+    let target = column as i64 + (to as i64 - from as i64) / 2;
+    (0..to as i64).contains(&target).then_some(target as usize)
+}
+
+/// Colonne(s) cible pour `column` quand on passe de `from` à `to` touches.
+pub fn convert_column(column: usize, from: usize, to: usize, strategy: KeycountStrategy) -> Vec<usize> {
+    if from == to || from == 0 || to == 0 {
+        return vec![column];
+    }
+    match strategy {
+        KeycountStrategy::Center => center(column, from, to).into_iter().collect(),
+        KeycountStrategy::LeftAlign => (column < to).then_some(column).into_iter().collect(),
+        KeycountStrategy::Spread => {
+            if from == 1 {
+                return vec![to / 2];
+            }
+            let ratio = (to - 1) as f64 / (from - 1) as f64;
+            vec![(column as f64 * ratio).round() as usize]
+        }
+        KeycountStrategy::MirrorDuplicate => {
+            let Some(target) = center(column, from, to) else {
+                return Vec::new();
+            };
+            let mirror = to - 1 - target;
+            if mirror == target {
+                vec![target]
+            } else {
+                vec![target, mirror]
+            }
+        }
+        KeycountStrategy::Merge => vec![(column * to / from).min(to - 1)],
+    }
+}
+
+/// Une fusion de colonnes peut empiler deux notes au même endroit ou poser une note dans le body
+/// d'un LN de la colonne voisine. Les doublons sont retirés, les LN raccourcis pour se relâcher
+/// `min_gap` avant la note suivante, ou la note retirée s'il ne reste pas de place pour le LN.
+/// Renvoie le nombre de notes retirées ou raccourcies.
+pub fn dedupe(hit_objects: &mut Vec<HitObject>, keycount: usize, min_gap: f64) -> usize {
+    hit_objects.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap());
+    let mut kept: Vec<HitObject> = Vec::with_capacity(hit_objects.len());
+    // Dernière note gardée de chaque colonne
+    let mut last: Vec<Option<usize>> = vec![None; keycount.max(1)];
+    let mut fixed = 0;
+    for hit_object in hit_objects.drain(..) {
+        let Some(column) = columns::column_of(&hit_object, keycount) else {
+            kept.push(hit_object);
+            continue;
+        };
+        if let Some(previous) = last[column].map(|idx| &mut kept[idx]) {
+            if hit_object.start_time - previous.start_time < 1.0 {
+                fixed += 1;
+                continue;
+            }
+            let previous_start = previous.start_time;
+            if let HitObjectKind::Hold(ref mut hold) = previous.kind {
+                let latest_end = hit_object.start_time - min_gap;
+                if previous_start + hold.duration > latest_end {
+                    fixed += 1;
+                    if latest_end - previous_start < 1.0 {
+                        continue;
+                    }
+                    hold.duration = latest_end - previous_start;
+                }
+            }
+        }
+        last[column] = Some(kept.len());
+        kept.push(hit_object);
+    }
+    *hit_objects = kept;
+    fixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosu_map::section::hit_objects::{HitObjectCircle, HitObjectHold};
+    use rosu_map::util::Pos;
+
+    fn note(time: f64, column: usize, keycount: usize) -> HitObject {
+        HitObject {
+            start_time: time,
+            kind: HitObjectKind::Circle(HitObjectCircle {
+                pos: Pos::new(columns::column_x(column, keycount), 192.0),
+                new_combo: false,
+                combo_offset: 0,
+            }),
+            samples: Vec::new(),
+        }
+    }
+
+    fn hold(time: f64, duration: f64, column: usize, keycount: usize) -> HitObject {
+        HitObject {
+            start_time: time,
+            kind: HitObjectKind::Hold(HitObjectHold {
+                pos_x: columns::column_x(column, keycount),
+                duration,
+            }),
+            samples: Vec::new(),
+        }
+    }
+
+    fn merge(hit_objects: &[HitObject], from: usize, to: usize) -> Vec<HitObject> {
+        let mut merged: Vec<HitObject> = hit_objects
+            .iter()
+            .flat_map(|h| {
+                let column = columns::column_of(h, from).unwrap();
+                convert_column(column, from, to, KeycountStrategy::Merge).into_iter().map(move |target| {
+                    let mut h = h.clone();
+                    columns::set_column(&mut h, target, to);
+                    h
+                })
+            })
+            .collect();
+        dedupe(&mut merged, to, 20.0);
+        merged
+    }
+
+    fn duration(hit_object: &HitObject) -> Option<f64> {
+        match &hit_object.kind {
+            HitObjectKind::Hold(h) => Some(h.duration),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn merge_shortens_a_hold_over_a_neighbouring_note() {
+        // 4K -> 2K : les colonnes 0 et 1 deviennent la colonne 0
+        let merged = merge(&[hold(0.0, 500.0, 0, 4), note(250.0, 1, 4)], 4, 2);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(duration(&merged[0]), Some(230.0));
+        assert_eq!(merged[1].start_time, 250.0);
+        assert_eq!(columns::column_of(&merged[1], 2), Some(0));
+    }
+
+    #[test]
+    fn merge_drops_a_note_with_no_room_for_the_hold() {
+        let merged = merge(&[hold(0.0, 500.0, 0, 4), note(10.0, 1, 4)], 4, 2);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(duration(&merged[0]), Some(500.0));
+    }
+
+    #[test]
+    fn merge_keeps_one_of_two_stacked_notes() {
+        let merged = merge(&[note(0.0, 0, 4), note(0.0, 1, 4), note(0.0, 2, 4)], 4, 2);
+        assert_eq!(merged.len(), 2);
+    }
+
+    #[test]
+    fn other_columns_are_untouched() {
+        let mut hit_objects = vec![hold(0.0, 500.0, 0, 4), note(250.0, 1, 4)];
+        assert_eq!(dedupe(&mut hit_objects, 4, 20.0), 0);
+        assert_eq!(duration(&hit_objects[0]), Some(500.0));
+    }
+}
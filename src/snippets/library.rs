@@ -0,0 +1,171 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_DIR: &str = "snippets";
+pub const SNIPPETS_EXTENSION: &str = "snippets";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LibraryKind {
+    /// Snippets de l'utilisateur
+    Personal,
+    /// Dossier partagé avec l'équipe (synchronisé, réseau...)
+    Shared,
+    /// Packs importés, jamais modifiés par l'app
+    ReadOnly,
+}
+
+impl LibraryKind {
+    pub const ALL: [LibraryKind; 3] = [
+        LibraryKind::Personal,
+        LibraryKind::Shared,
+        LibraryKind::ReadOnly,
+    ];
+
+    /// Nom utilisé dans settings.ini
+    pub fn name(&self) -> &'static str {
+        match self {
+            LibraryKind::Personal => "personal",
+            LibraryKind::Shared => "shared",
+            LibraryKind::ReadOnly => "readonly",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.name() == name)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LibraryKind::Personal => "Personal",
+            LibraryKind::Shared => "Team shared",
+            LibraryKind::ReadOnly => "Read-only",
+        }
+    }
+
+    pub fn is_writable(&self) -> bool {
+        *self != LibraryKind::ReadOnly
+    }
+}
+
+/// Un dossier de snippets, scanné avec ses sous-dossiers.
+#[derive(Clone, Debug)]
+pub struct LibraryRoot {
+    pub path: PathBuf,
+    pub kind: LibraryKind,
+}
+
+impl LibraryRoot {
+    pub fn new(path: PathBuf, kind: LibraryKind) -> Self {
+        Self { path, kind }
+    }
+
+    /// `snippets/` du dossier courant s'il existe (lancement depuis le repo),
+    /// sinon celui à côté de l'exécutable.
+    pub fn default_personal() -> Self {
+        let path = if Path::new(DEFAULT_DIR).is_dir() {
+            PathBuf::from(DEFAULT_DIR)
+        } else {
+            std::env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(|dir| dir.join(DEFAULT_DIR)))
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_DIR))
+        };
+        Self::new(path, LibraryKind::Personal)
+    }
+
+    pub fn label(&self) -> String {
+        format!("{} ({})", self.kind.label(), self.path.display())
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        path.starts_with(&self.path)
+    }
+}
+
+/// Un fichier .snippets trouvé pendant le scan.
+#[derive(Clone, Debug)]
+pub struct LibraryFile {
+    /// Index dans la liste des racines
+    pub root: usize,
+    pub path: PathBuf,
+    /// Sous-dossier relatif à la racine, `a/b`, vide à la racine
+    pub category: String,
+}
+
+impl LibraryFile {
+    pub fn file_name(&self) -> String {
+        self.path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string()
+    }
+}
+
+fn scan_dir(root: usize, base: &Path, dir: &Path, files: &mut Vec<LibraryFile>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // file_type ne suit pas les liens : un lien vers un dossier parent bouclerait à l'infini
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            scan_dir(root, base, &path, files);
+        } else if !path.is_dir() && path.extension().and_then(|e| e.to_str()) == Some(SNIPPETS_EXTENSION) {
+            let category = path
+                .parent()
+                .and_then(|p| p.strip_prefix(base).ok())
+                .map(|p| p.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+                .unwrap_or_default();
+            files.push(LibraryFile { root, path, category });
+        }
+    }
+}
+
+/// Tous les .snippets des racines, sous-dossiers compris, triés par racine puis chemin.
+pub fn scan(roots: &[LibraryRoot]) -> Vec<LibraryFile> {
+    let mut files = Vec::new();
+    for (idx, root) in roots.iter().enumerate() {
+        scan_dir(idx, &root.path, &root.path, &mut files);
+    }
+    files.sort_by(|a, b| (a.root, &a.path).cmp(&(b.root, &b.path)));
+    files
+}
+
+/// La racine qui contient `path`, pour savoir si on a le droit de le réécrire.
+pub fn root_of<'a>(roots: &'a [LibraryRoot], path: &Path) -> Option<&'a LibraryRoot> {
+    roots.iter().find(|r| r.contains(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_skips_symlinked_directories() {
+        let base = std::env::temp_dir().join(format!("rosu-snippets-scan-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("jacks")).unwrap();
+        fs::write(base.join("jacks").join("a.snippets"), "").unwrap();
+        fs::write(base.join("b.snippets"), "").unwrap();
+        // Boucle vers la racine et lien vers un fichier
+        std::os::unix::fs::symlink(&base, base.join("jacks").join("loop")).unwrap();
+        std::os::unix::fs::symlink(base.join("b.snippets"), base.join("c.snippets")).unwrap();
+
+        let files = scan(&[LibraryRoot::new(base.clone(), LibraryKind::Personal)]);
+        let _ = fs::remove_dir_all(&base);
+
+        let names: Vec<(String, String)> = files
+            .iter()
+            .map(|f| (f.category.clone(), f.path.file_name().unwrap().to_string_lossy().to_string()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (String::new(), "b.snippets".to_string()),
+                (String::new(), "c.snippets".to_string()),
+                ("jacks".to_string(), "a.snippets".to_string()),
+            ]
+        );
+    }
+}
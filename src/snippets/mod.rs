@@ -1 +1,18 @@
-pub mod structs;
\ No newline at end of file
+pub mod structs;
+pub mod history;
+pub mod timing;
+pub mod snap;
+pub mod conflict;
+pub mod keycount;
+pub mod columns;
+pub mod transform;
+pub mod rhythm;
+pub mod hitsound;
+pub mod format;
+pub mod pack;
+pub mod library;
+pub mod index;
+pub mod watcher;
+pub mod search;
+pub mod tags;
+pub mod pattern;
@@ -0,0 +1,226 @@
+use eyre::Result;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::snippets::format;
+use crate::snippets::index::IndexEntry;
+use crate::utils;
+
+const PACK_HEADER: &str = "rosu-snippets pack v1";
+pub const PACK_EXTENSION: &str = "snippetpack";
+
+#[derive(Clone, Debug, Default)]
+pub struct PackManifest {
+    pub name: String,
+    pub author: String,
+    pub description: String,
+    pub created_at: u64,
+}
+
+/// Un fichier .snippets tel qu'il est stocké dans le pack.
+#[derive(Clone, Debug)]
+pub struct PackEntry {
+    pub file_name: String,
+    pub content: String,
+}
+
+impl PackEntry {
+    pub fn hash(&self) -> u64 {
+        utils::content_hash(&self.content)
+    }
+}
+
+/// Un seul fichier texte : le manifest, puis chaque .snippets précédé de sa taille en octets.
+#[derive(Clone, Debug, Default)]
+pub struct Pack {
+    pub manifest: PackManifest,
+    pub entries: Vec<PackEntry>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ImportReport {
+    pub imported: Vec<String>,
+    /// Pattern déjà présent dans une bibliothèque, sous n'importe quel nom
+    pub duplicates: Vec<String>,
+    /// (nom dans le pack, nom donné à l'import) quand un autre fichier avait déjà ce nom
+    pub collisions: Vec<(String, String)>,
+}
+
+impl ImportReport {
+    pub fn summary(&self) -> String {
+        let mut summary = format!("Imported {} snippets", self.imported.len());
+        if !self.duplicates.is_empty() {
+            summary.push_str(&format!(", {} already present", self.duplicates.len()));
+        }
+        if !self.collisions.is_empty() {
+            let renamed: Vec<String> = self.collisions.iter().map(|(from, to)| format!("{} -> {}", from, to)).collect();
+            summary.push_str(&format!(", renamed {}", renamed.join(", ")));
+        }
+        summary
+    }
+}
+
+impl Pack {
+    pub fn new(name: &str) -> Self {
+        Self {
+            manifest: PackManifest {
+                name: name.to_string(),
+                ..PackManifest::default()
+            },
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, file_name: &str, content: String) {
+        self.entries.push(PackEntry {
+            file_name: file_name.to_string(),
+            content,
+        });
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("{}\n", PACK_HEADER));
+        out.push_str(&format!("Name: {}\n", self.manifest.name));
+        out.push_str(&format!("Author: {}\n", self.manifest.author));
+        out.push_str(&format!("Description: {}\n", self.manifest.description.replace('\n', " ")));
+        out.push_str(&format!("Created: {}\n", self.manifest.created_at));
+        out.push_str(&format!("Entries: {}\n", self.entries.len()));
+        for entry in &self.entries {
+            out.push_str(&format!("\nEntry: {}\n", entry.file_name));
+            out.push_str(&format!("Hash: {:016x}\n", entry.hash()));
+            out.push_str(&format!("Length: {}\n", entry.content.len()));
+            out.push_str(&entry.content);
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn from_text(text: &str) -> Result<Self> {
+        let mut pos = 0;
+        let next_line = |pos: &mut usize| -> Option<&str> {
+            let rest = text.get(*pos..)?;
+            if rest.is_empty() {
+                return None;
+            }
+            let end = rest.find('\n').unwrap_or(rest.len());
+            *pos += (end + 1).min(rest.len());
+            Some(rest[..end].trim_end_matches('\r'))
+        };
+
+        if next_line(&mut pos) != Some(PACK_HEADER) {
+            return Err(eyre::eyre!("Not a snippet pack"));
+        }
+        let mut pack = Pack::default();
+        let mut expected = 0;
+        while let Some(line) = next_line(&mut pos) {
+            if line.is_empty() {
+                break;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key {
+                "Name" => pack.manifest.name = value.to_string(),
+                "Author" => pack.manifest.author = value.to_string(),
+                "Description" => pack.manifest.description = value.to_string(),
+                "Created" => pack.manifest.created_at = value.parse().unwrap_or_default(),
+                "Entries" => expected = value.parse()?,
+                _ => {}
+            }
+        }
+
+        for _ in 0..expected {
+            let mut field = |name: &str| -> Result<String> {
+                let line = next_line(&mut pos).ok_or_else(|| eyre::eyre!("Truncated pack"))?;
+                line.strip_prefix(name)
+                    .and_then(|l| l.strip_prefix(':'))
+                    .map(|v| v.trim().to_string())
+                    .ok_or_else(|| eyre::eyre!("Expected {} in pack, got {}", name, line))
+            };
+            let file_name = field("Entry")?;
+            let hash = u64::from_str_radix(&field("Hash")?, 16)?;
+            let length: usize = field("Length")?.parse()?;
+            let end = pos
+                .checked_add(length)
+                .ok_or_else(|| eyre::eyre!("Invalid length for entry {}", file_name))?;
+            let content = text
+                .get(pos..end)
+                .ok_or_else(|| eyre::eyre!("Truncated entry {}", file_name))?
+                .to_string();
+            pos = end + 1;
+            // Ligne vide entre deux entrées
+            pos += text.get(pos..).map(|r| r.starts_with('\n') as usize).unwrap_or(0);
+
+            let entry = PackEntry { file_name, content };
+            if entry.hash() != hash {
+                return Err(eyre::eyre!("Corrupted entry {} (hash mismatch)", entry.file_name));
+            }
+            pack.entries.push(entry);
+        }
+        if pack.entries.len() != expected {
+            return Err(eyre::eyre!("Pack declares {} entries, found {}", expected, pack.entries.len()));
+        }
+        Ok(pack)
+    }
+
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        self.manifest.created_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::from_text(&fs::read_to_string(path)?)
+    }
+
+    /// Copie les entrées dans `snippets_dir`. Chaque entrée doit être une snippet lisible.
+    /// Les patterns déjà présents dans une des bibliothèques (`library`) sont ignorés,
+    /// les noms déjà pris dans `snippets_dir` sont renommés.
+    pub fn import_into(&self, snippets_dir: &Path, library: &[IndexEntry]) -> Result<ImportReport> {
+        let mut hashes = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            let hash = format::read_any(&entry.content)
+                .and_then(|_| format::pattern_hash(&entry.content))
+                .map_err(|e| eyre::eyre!("Invalid snippet {} in pack: {}", entry.file_name, e))?;
+            hashes.push(hash);
+        }
+
+        fs::create_dir_all(snippets_dir)?;
+        let mut existing_hashes: Vec<u64> = library.iter().map(|e| e.hash).collect();
+        let mut existing_names: Vec<String> = fs::read_dir(snippets_dir)?
+            .flatten()
+            .filter_map(|e| e.file_name().to_str().map(str::to_string))
+            .collect();
+
+        let mut report = ImportReport::default();
+        for (entry, hash) in self.entries.iter().zip(hashes) {
+            if existing_hashes.contains(&hash) {
+                report.duplicates.push(entry.file_name.clone());
+                continue;
+            }
+            // Pas de chemin dans les noms venant d'un pack
+            let file_name = Path::new(&entry.file_name)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("imported.snippets")
+                .to_string();
+            let mut target = file_name.clone();
+            let stem = file_name.trim_end_matches(".snippets");
+            let mut n = 2;
+            while existing_names.contains(&target) {
+                target = format!("{} ({}).snippets", stem, n);
+                n += 1;
+            }
+            if target != file_name {
+                report.collisions.push((file_name.clone(), target.clone()));
+            }
+            fs::write(snippets_dir.join(&target), &entry.content)?;
+            existing_hashes.push(hash);
+            existing_names.push(target.clone());
+            report.imported.push(target);
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,570 @@
+use eyre::Result;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use rosu_map::section::hit_objects::{HitObject, HitObjectKind};
+use crate::snippets::columns;
+use crate::snippets::format;
+use crate::snippets::index::{self, IndexEntry};
+use crate::snippets::library::{self, LibraryRoot};
+use crate::snippets::structs::Snippets;
+use crate::snippets::tags;
+
+/// Notes à moins de 2ms l'une de l'autre forment un seul accord
+const CHORD_TOLERANCE: f64 = 2.0;
+/// En dessous de 6 accords il n'y a pas assez de notes pour parler de pattern
+const MIN_ROWS: usize = 6;
+/// Confiance minimale pour proposer un tag
+pub const SUGGEST_THRESHOLD: f64 = 0.5;
+
+/// Familles de patterns mania reconnues par l'analyse.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pattern {
+    Jumpstream,
+    Handstream,
+    Chordjack,
+    Jacks,
+    Trills,
+    Rolls,
+    Stairs,
+    Brackets,
+    LnInverse,
+    LnRelease,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 10] = [
+        Pattern::Jumpstream,
+        Pattern::Handstream,
+        Pattern::Chordjack,
+        Pattern::Jacks,
+        Pattern::Trills,
+        Pattern::Rolls,
+        Pattern::Stairs,
+        Pattern::Brackets,
+        Pattern::LnInverse,
+        Pattern::LnRelease,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Pattern::Jumpstream => "Jumpstream",
+            Pattern::Handstream => "Handstream",
+            Pattern::Chordjack => "Chordjack",
+            Pattern::Jacks => "Jacks",
+            Pattern::Trills => "Trills",
+            Pattern::Rolls => "Rolls",
+            Pattern::Stairs => "Stairs",
+            Pattern::Brackets => "Brackets",
+            Pattern::LnInverse => "LN inverse",
+            Pattern::LnRelease => "LN release",
+        }
+    }
+
+    /// Tag ajouté aux snippets, les LN sont rangées sous `ln/`
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Pattern::Jumpstream => "jumpstream",
+            Pattern::Handstream => "handstream",
+            Pattern::Chordjack => "chordjack",
+            Pattern::Jacks => "jacks",
+            Pattern::Trills => "trills",
+            Pattern::Rolls => "rolls",
+            Pattern::Stairs => "stairs",
+            Pattern::Brackets => "brackets",
+            Pattern::LnInverse => "ln/inverse",
+            Pattern::LnRelease => "ln/release",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PatternScore {
+    pub pattern: Pattern,
+    /// Entre 0 et 1
+    pub confidence: f64,
+}
+
+/// Toutes les notes qui tombent au même moment.
+struct Row {
+    time: f64,
+    columns: Vec<usize>,
+}
+
+struct Hold {
+    column: usize,
+    end: f64,
+}
+
+fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    count as f64 / total as f64
+}
+
+/// `value` ramené entre 0 et 1, `full` donnant une confiance de 1
+fn scale(value: f64, full: f64) -> f64 {
+    (value / full).clamp(0.0, 1.0)
+}
+
+fn rows(hit_objects: &[HitObject], keycount: usize) -> Vec<Row> {
+    let mut notes: Vec<(f64, usize)> = hit_objects
+        .iter()
+        .filter_map(|h| columns::column_of(h, keycount).map(|c| (h.start_time, c)))
+        .collect();
+    notes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let mut rows: Vec<Row> = Vec::new();
+    for (time, column) in notes {
+        match rows.last_mut() {
+            Some(row) if time - row.time <= CHORD_TOLERANCE => {
+                if !row.columns.contains(&column) {
+                    row.columns.push(column);
+                }
+            }
+            _ => rows.push(Row { time, columns: vec![column] }),
+        }
+    }
+    for row in rows.iter_mut() {
+        row.columns.sort();
+    }
+    rows
+}
+
+/// Accord de deux colonnes voisines, `[12]`
+fn is_adjacent_jump(row: &Row) -> bool {
+    row.columns.len() == 2 && row.columns[1] - row.columns[0] == 1
+}
+
+/// Trills : une note sur deux revient sur la même colonne, `1212`.
+fn trills(steps: &[Option<i64>]) -> f64 {
+    if steps.len() < 3 {
+        return 0.0;
+    }
+    let alternating = steps
+        .windows(3)
+        .filter(|w| match (w[0], w[1], w[2]) {
+            (Some(a), Some(b), Some(c)) => a != 0 && b == -a && c == a,
+            _ => false,
+        })
+        .count();
+    scale(ratio(alternating, steps.len() - 2), 0.5)
+}
+
+/// Escaliers `1234321` et rolls `12341234` : des colonnes voisines dans le même sens.
+/// Ce qui les sépare, c'est la fin d'une montée : demi-tour pour un escalier, retour au début pour un roll.
+fn stairs_and_rolls(steps: &[Option<i64>]) -> (f64, f64) {
+    let unit = |step: Option<i64>| step.filter(|d| d.abs() == 1);
+    let continuing = steps
+        .windows(2)
+        .filter(|w| matches!((unit(w[0]), unit(w[1])), (Some(a), Some(b)) if a == b))
+        .count();
+    let share = scale(ratio(continuing, steps.len()), 0.5);
+
+    let mut bounces = 0;
+    let mut wraps = 0;
+    for (i, step) in steps.iter().enumerate() {
+        let Some(direction) = unit(*step) else {
+            continue;
+        };
+        match steps.get(i + 1).copied().flatten() {
+            Some(next) if next == -direction => bounces += 1,
+            Some(next) if next.abs() >= 2 && next.signum() == -direction => {
+                if unit(steps.get(i + 2).copied().flatten()) == Some(direction) {
+                    wraps += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    if bounces + wraps == 0 {
+        return (share, 0.0);
+    }
+    (share * ratio(bounces, bounces + wraps), share * ratio(wraps, bounces + wraps))
+}
+
+/// LN inverse : chaque hold se relâche juste avant la note suivante de sa colonne.
+/// LN release : les relâchements tombent hors des accords, ils forment leur propre rythme.
+fn long_notes(snippet: &Snippets, keycount: usize, rows: &[Row]) -> (f64, f64) {
+    let holds: Vec<Hold> = snippet
+        .hit_objects
+        .iter()
+        .filter_map(|h| match &h.kind {
+            HitObjectKind::Hold(hold) => Some(Hold {
+                column: columns::column_of(h, keycount)?,
+                end: h.start_time + hold.duration,
+            }),
+            _ => None,
+        })
+        .collect();
+    let note_count: usize = rows.iter().map(|r| r.columns.len()).sum();
+    let hold_share = ratio(holds.len(), note_count);
+    if holds.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    // Les accords sont triés : on saute directement à ceux qui suivent la fin du hold
+    let after = |end: f64| rows.partition_point(|r| r.time < end - CHORD_TOLERANCE);
+
+    // Un écart d'une demi-temps au plus entre la fin du hold et la note suivante
+    let max_gap = snippet.base_beat_len() / 2.0 + CHORD_TOLERANCE;
+    let inversed = holds
+        .iter()
+        .filter(|hold| {
+            rows[after(hold.end)..]
+                .iter()
+                .skip_while(|r| r.time <= hold.end - CHORD_TOLERANCE)
+                .take_while(|r| r.time - hold.end <= max_gap)
+                .any(|r| r.columns.contains(&hold.column))
+        })
+        .count();
+    let inverse = scale(hold_share, 0.7) * scale(ratio(inversed, holds.len()), 0.6);
+
+    let off_row = holds
+        .iter()
+        .filter(|hold| !rows.get(after(hold.end)).is_some_and(|r| r.time - hold.end <= CHORD_TOLERANCE))
+        .count();
+    let release = scale(hold_share, 0.4) * scale(ratio(off_row, holds.len()), 0.6) * (1.0 - inverse);
+    (inverse, release)
+}
+
+/// Analyse les notes de la snippet, du pattern le plus probable au moins probable.
+/// Les patterns avec une confiance nulle ne sont pas renvoyés.
+pub fn analyse(snippet: &Snippets) -> Vec<PatternScore> {
+    let keycount = snippet.keycount.max(1);
+    let rows = rows(&snippet.hit_objects, keycount);
+    if rows.len() < MIN_ROWS {
+        return Vec::new();
+    }
+
+    let pairs: Vec<(&Row, &Row)> = rows.windows(2).map(|w| (&w[0], &w[1])).collect();
+    let jacked = pairs
+        .iter()
+        .filter(|(a, b)| a.columns.iter().any(|c| b.columns.contains(c)))
+        .count();
+    let jack_share = ratio(jacked, pairs.len());
+    let stream = scale(1.0 - jack_share, 0.8);
+    let share_of = |matches: fn(usize) -> bool| ratio(rows.iter().filter(|r| matches(r.columns.len())).count(), rows.len());
+    let singles = share_of(|n| n == 1);
+    let jumps = share_of(|n| n == 2);
+    let hands = share_of(|n| n == 3);
+    let chords = share_of(|n| n >= 2);
+
+    // Écart de colonne entre deux notes seules consécutives
+    let steps: Vec<Option<i64>> = pairs
+        .iter()
+        .map(|(a, b)| {
+            (a.columns.len() == 1 && b.columns.len() == 1).then(|| b.columns[0] as i64 - a.columns[0] as i64)
+        })
+        .collect();
+    let (stairs, rolls) = stairs_and_rolls(&steps);
+
+    // [12]3 ou [12][34] : un accord voisin enchaîné sans jack sur un bloc de colonnes continu
+    let bracketed = pairs
+        .iter()
+        .filter(|(a, b)| {
+            if !(is_adjacent_jump(a) || is_adjacent_jump(b)) || a.columns.iter().any(|c| b.columns.contains(c)) {
+                return false;
+            }
+            let mut block: Vec<usize> = a.columns.iter().chain(&b.columns).copied().collect();
+            block.sort();
+            block.windows(2).all(|w| w[1] - w[0] == 1)
+        })
+        .count();
+    let (inverse, release) = long_notes(snippet, keycount, &rows);
+
+    let mut scores: Vec<PatternScore> = Pattern::ALL
+        .into_iter()
+        .map(|pattern| {
+            let confidence = match pattern {
+                Pattern::Jumpstream => scale(jumps, 0.35) * stream * (1.0 - scale(hands, 0.25)),
+                Pattern::Handstream => scale(hands, 0.25) * stream,
+                Pattern::Chordjack => scale(chords, 0.6) * scale(jack_share, 0.5),
+                Pattern::Jacks => scale(singles, 0.6) * scale(jack_share, 0.4),
+                Pattern::Trills => trills(&steps),
+                Pattern::Rolls => rolls,
+                Pattern::Stairs => stairs,
+                Pattern::Brackets => scale(ratio(bracketed, pairs.len()), 0.5),
+                Pattern::LnInverse => inverse,
+                Pattern::LnRelease => release,
+            };
+            PatternScore { pattern, confidence }
+        })
+        .filter(|s| s.confidence > 0.0)
+        .collect();
+    scores.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap());
+    scores
+}
+
+/// Patterns de `scores` au-dessus de `threshold` dont `tags` ne contient pas encore le tag.
+fn filter_suggestions(scores: &[PatternScore], tags: &[String], threshold: f64) -> Vec<PatternScore> {
+    scores
+        .iter()
+        .filter(|s| s.confidence >= threshold)
+        .filter(|s| !tags.iter().any(|t| tags::tag_matches(t, s.pattern.tag())))
+        .copied()
+        .collect()
+}
+
+/// Patterns détectés au-dessus de `threshold` dont la snippet n'a pas encore le tag.
+pub fn suggestions(snippet: &Snippets, threshold: f64) -> Vec<PatternScore> {
+    filter_suggestions(&analyse(snippet), &snippet.tags, threshold)
+}
+
+/// Empreinte de tout ce que lit l'analyse, pour savoir quand la refaire.
+fn fingerprint(snippet: &Snippets) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    snippet.keycount.hash(&mut hasher);
+    snippet.base_beat_len().to_bits().hash(&mut hasher);
+    for h in &snippet.hit_objects {
+        h.start_time.to_bits().hash(&mut hasher);
+        columns::column_of(h, snippet.keycount).hash(&mut hasher);
+        if let HitObjectKind::Hold(hold) = &h.kind {
+            hold.duration.to_bits().hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Dernière analyse affichée, refaite seulement quand les notes changent.
+#[derive(Clone, Debug, Default)]
+pub struct PatternCache {
+    fingerprint: Option<u64>,
+    scores: Vec<PatternScore>,
+}
+
+impl PatternCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn suggestions(&mut self, snippet: &Snippets, threshold: f64) -> Vec<PatternScore> {
+        let fingerprint = fingerprint(snippet);
+        if self.fingerprint != Some(fingerprint) {
+            self.scores = analyse(snippet);
+            self.fingerprint = Some(fingerprint);
+        }
+        filter_suggestions(&self.scores, &snippet.tags, threshold)
+    }
+}
+
+/// Un fichier réécrit par l'auto-tag.
+#[derive(Clone, Debug)]
+pub struct TaggedFile {
+    pub path: PathBuf,
+    pub added: Vec<String>,
+    /// Date de modification juste après notre écriture
+    pub mtime: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AutoTagReport {
+    /// Fichiers réécrits avec les tags ajoutés
+    pub tagged: Vec<TaggedFile>,
+    pub read_only: usize,
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl AutoTagReport {
+    pub fn summary(&self) -> String {
+        let added: usize = self.tagged.iter().map(|t| t.added.len()).sum();
+        let mut summary = format!("Added {} tags to {} files", added, self.tagged.len());
+        if self.read_only > 0 {
+            summary.push_str(&format!(", {} read-only skipped", self.read_only));
+        }
+        if !self.failed.is_empty() {
+            summary.push_str(&format!(", {} failed", self.failed.len()));
+        }
+        summary
+    }
+}
+
+fn tag_file(path: &Path, threshold: f64) -> Result<Vec<String>> {
+    let mut snippet = format::read_any(&fs::read_to_string(path)?)?;
+    let added: Vec<String> = suggestions(&snippet, threshold)
+        .into_iter()
+        .map(|s| s.pattern.tag().to_string())
+        .collect();
+    if !added.is_empty() {
+        snippet.tags.extend(added.iter().cloned());
+        fs::write(path, format::write(&snippet)?)?;
+    }
+    Ok(added)
+}
+
+/// Ajoute les tags détectés à toutes les snippets des bibliothèques modifiables.
+/// Les tags existants ne sont jamais retirés.
+pub fn tag_library(entries: &[IndexEntry], roots: &[LibraryRoot], threshold: f64) -> AutoTagReport {
+    let mut report = AutoTagReport::default();
+    for entry in entries {
+        if library::root_of(roots, &entry.path).is_some_and(|r| !r.kind.is_writable()) {
+            report.read_only += 1;
+            continue;
+        }
+        match tag_file(&entry.path, threshold) {
+            Ok(added) if added.is_empty() => {}
+            Ok(added) => report.tagged.push(TaggedFile {
+                path: entry.path.clone(),
+                added,
+                mtime: index::modified_time(&entry.path),
+            }),
+            Err(e) => report.failed.push((entry.path.clone(), e.to_string())),
+        }
+    }
+    report
+}
+
+/// Lance l'auto-tag dans un thread, le rapport arrive par le channel une fois fini.
+pub fn spawn_tag_library(entries: Vec<IndexEntry>, roots: Vec<LibraryRoot>, threshold: f64) -> Receiver<AutoTagReport> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(tag_library(&entries, &roots, threshold));
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosu_map::section::hit_objects::{HitObjectCircle, HitObjectHold};
+    use rosu_map::util::Pos;
+
+    /// Un roll 4K dont chaque note est un hold relâché 100ms avant la note suivante de sa colonne.
+    fn inverse_roll() -> Snippets {
+        let mut snippet = Snippets::new();
+        snippet.keycount = 4;
+        snippet.hit_objects = (0..16)
+            .map(|i| HitObject {
+                start_time: i as f64 * 150.0,
+                kind: HitObjectKind::Hold(HitObjectHold {
+                    pos_x: columns::column_x(i % 4, 4),
+                    duration: 500.0,
+                }),
+                samples: Vec::new(),
+            })
+            .collect();
+        snippet
+    }
+
+    /// Accords de notes 4K espacés de 100ms, une liste de colonnes par accord.
+    fn rows_of(chords: &[&[usize]]) -> Snippets {
+        let mut snippet = Snippets::new();
+        snippet.keycount = 4;
+        for (i, chord) in chords.iter().enumerate() {
+            for column in chord.iter() {
+                snippet.hit_objects.push(HitObject {
+                    start_time: i as f64 * 100.0,
+                    kind: HitObjectKind::Circle(HitObjectCircle {
+                        pos: Pos::new(columns::column_x(*column, 4), 192.0),
+                        new_combo: false,
+                        combo_offset: 0,
+                    }),
+                    samples: Vec::new(),
+                });
+            }
+        }
+        snippet
+    }
+
+    /// Holds courts relâchés entre deux accords, loin de la note suivante de leur colonne.
+    fn release_holds() -> Snippets {
+        let mut snippet = rows_of(&[&[0], &[2], &[1], &[3], &[0], &[2], &[1], &[3], &[0], &[2], &[1], &[3]]);
+        for hit_object in snippet.hit_objects.iter_mut() {
+            let pos_x = columns::column_x(columns::column_of(hit_object, 4).unwrap(), 4);
+            hit_object.kind = HitObjectKind::Hold(HitObjectHold { pos_x, duration: 50.0 });
+        }
+        snippet
+    }
+
+    fn patterns(scores: &[PatternScore]) -> Vec<Pattern> {
+        scores.iter().map(|s| s.pattern).collect()
+    }
+
+    #[test]
+    fn each_pattern_ranks_first_on_its_fixture() {
+        let cases = [
+            (
+                rows_of(&[&[0, 2], &[1], &[0, 3], &[1], &[0, 2], &[1], &[0, 3], &[1], &[0, 2], &[1], &[0, 3], &[1]]),
+                vec![Pattern::Jumpstream],
+            ),
+            (
+                rows_of(&[&[0, 1, 3], &[2], &[0, 1], &[3], &[0, 1, 2], &[3], &[1, 2], &[0]]),
+                vec![Pattern::Handstream, Pattern::Brackets],
+            ),
+            (
+                rows_of(&[&[0, 1], &[0, 2], &[0, 1], &[0, 2], &[0, 1], &[0, 2], &[0, 1], &[0, 2]]),
+                vec![Pattern::Chordjack],
+            ),
+            (
+                rows_of(&[&[0], &[0], &[1], &[1], &[2], &[2], &[3], &[3]]),
+                vec![Pattern::Jacks],
+            ),
+            (
+                rows_of(&[&[0], &[1], &[0], &[1], &[0], &[1], &[0], &[1]]),
+                vec![Pattern::Trills],
+            ),
+            (
+                rows_of(&[&[0], &[1], &[2], &[3], &[0], &[1], &[2], &[3], &[0], &[1], &[2], &[3]]),
+                vec![Pattern::Rolls],
+            ),
+            (
+                rows_of(&[&[0], &[1], &[2], &[3], &[2], &[1], &[0], &[1], &[2], &[3], &[2], &[1]]),
+                vec![Pattern::Stairs],
+            ),
+            (
+                rows_of(&[&[0, 1], &[2], &[3], &[1, 2], &[0], &[3], &[0, 1], &[2], &[3], &[1, 2], &[0], &[3]]),
+                vec![Pattern::Brackets, Pattern::Jumpstream],
+            ),
+            (release_holds(), vec![Pattern::LnRelease]),
+        ];
+        for (snippet, expected) in cases {
+            let scores = analyse(&snippet);
+            assert_eq!(patterns(&scores), expected);
+            assert!(scores[0].confidence > SUGGEST_THRESHOLD);
+        }
+    }
+
+    #[test]
+    fn too_few_rows_are_not_analysed() {
+        assert!(analyse(&rows_of(&[&[0], &[1], &[0], &[1], &[0]])).is_empty());
+    }
+
+    fn confidence(scores: &[PatternScore], pattern: Pattern) -> f64 {
+        scores.iter().find(|s| s.pattern == pattern).map(|s| s.confidence).unwrap_or(0.0)
+    }
+
+    #[test]
+    fn holds_released_before_the_next_note_are_inverse() {
+        let scores = analyse(&inverse_roll());
+        assert!(confidence(&scores, Pattern::LnInverse) > 0.99);
+        assert_eq!(confidence(&scores, Pattern::LnRelease), 0.0);
+    }
+
+    #[test]
+    fn cache_follows_notes_and_tags() {
+        let mut snippet = inverse_roll();
+        let mut cache = PatternCache::new();
+        assert_eq!(cache.suggestions(&snippet, SUGGEST_THRESHOLD), suggestions(&snippet, SUGGEST_THRESHOLD));
+
+        snippet.tags.push("ln/inverse".to_string());
+        let cached = cache.suggestions(&snippet, SUGGEST_THRESHOLD);
+        assert!(cached.iter().all(|s| s.pattern != Pattern::LnInverse));
+
+        for hit_object in snippet.hit_objects.iter_mut() {
+            let x = columns::column_x(columns::column_of(hit_object, 4).unwrap(), 4);
+            hit_object.kind = HitObjectKind::Circle(HitObjectCircle {
+                pos: Pos::new(x, 192.0),
+                new_combo: false,
+                combo_offset: 0,
+            });
+        }
+        snippet.tags.clear();
+        let cached = cache.suggestions(&snippet, SUGGEST_THRESHOLD);
+        assert_eq!(cached, suggestions(&snippet, SUGGEST_THRESHOLD));
+        assert!(cached.iter().all(|s| s.pattern != Pattern::LnInverse));
+    }
+}
@@ -0,0 +1,305 @@
+use rosu_map::section::hit_objects::{HitObject, HitObjectCircle, HitObjectHold, HitObjectKind};
+use rosu_map::section::hit_objects::hit_samples::HitSampleInfo;
+use rosu_map::util::Pos;
+use crate::snippets::columns;
+use crate::snippets::conflict::end_time;
+use crate::snippets::structs::Snippets;
+use crate::snippets::timing::Timed;
+use crate::snippets::transform::{chords, RELEASE_GAP};
+
+/// Transformations rythmiques : chacune produit une nouvelle snippet non sauvegardée.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RhythmTransform {
+    Stretch(f64),
+    Reverse,
+    HoldsToRice,
+    RiceToHolds(f64),
+    HalveDensity,
+    DoubleDensity,
+}
+
+impl RhythmTransform {
+    pub fn label(&self) -> String {
+        match self {
+            RhythmTransform::Stretch(ratio) => format!("stretched x{:.2}", ratio),
+            RhythmTransform::Reverse => "reversed".to_string(),
+            RhythmTransform::HoldsToRice => "rice".to_string(),
+            RhythmTransform::RiceToHolds(len) => format!("LN {:.0}ms", len),
+            RhythmTransform::HalveDensity => "half density".to_string(),
+            RhythmTransform::DoubleDensity => "double density".to_string(),
+        }
+    }
+
+    pub fn apply(&self, snippet: &Snippets) -> Snippets {
+        let mut result = snippet.clone();
+        result.name = format!("{} ({})", snippet.name, self.label());
+        result.is_saved = false;
+        result.file_path = None;
+        result.stale = false;
+        match *self {
+            RhythmTransform::Stretch(ratio) => stretch(&mut result, ratio),
+            RhythmTransform::Reverse => reverse(&mut result),
+            RhythmTransform::HoldsToRice => holds_to_rice(&mut result),
+            RhythmTransform::RiceToHolds(len) => rice_to_holds(&mut result, len),
+            RhythmTransform::HalveDensity => halve_density(&mut result),
+            RhythmTransform::DoubleDensity => double_density(&mut result),
+        }
+        sort_hit_objects(&mut result.hit_objects);
+        result
+    }
+}
+
+fn sort_hit_objects(hit_objects: &mut [HitObject]) {
+    hit_objects.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap());
+}
+
+fn circle(start_time: f64, x: f32, samples: Vec<HitSampleInfo>) -> HitObject {
+    HitObject {
+        start_time,
+        kind: HitObjectKind::Circle(HitObjectCircle {
+            pos: Pos::new(x, 192.0),
+            new_combo: false,
+            combo_offset: 0,
+        }),
+        samples,
+    }
+}
+
+/// Étire ou compresse le pattern, ex: 4/3 pour passer d'un feeling 1/4 à 1/3.
+fn stretch(snippet: &mut Snippets, ratio: f64) {
+    for hit_object in snippet.hit_objects.iter_mut() {
+        hit_object.start_time *= ratio;
+        if let HitObjectKind::Hold(ref mut h) = hit_object.kind {
+            h.duration *= ratio;
+        }
+    }
+    for t in snippet.timing_points.iter_mut() {
+        t.time *= ratio;
+    }
+    for d in snippet.difficulty_points.iter_mut() {
+        d.time *= ratio;
+    }
+    for s in snippet.sample_points.iter_mut() {
+        s.time *= ratio;
+    }
+}
+
+/// Un point vaut jusqu'au suivant : à l'envers, il commence là où le suivant commençait.
+fn reverse_points<T: Timed>(points: &mut [T], duration: f64) {
+    let ends: Vec<f64> = points.iter().skip(1).map(|p| p.time()).chain([duration]).collect();
+    for (point, end) in points.iter_mut().zip(ends) {
+        point.set_time((duration - end.min(duration)).max(0.0));
+    }
+    points.reverse();
+}
+
+/// Joue le pattern à l'envers : la fin d'un LN devient son début.
+/// Les changements de bpm, de SV et de hitsounds suivent les notes qu'ils couvraient.
+fn reverse(snippet: &mut Snippets) {
+    let duration = snippet.duration();
+    for hit_object in snippet.hit_objects.iter_mut() {
+        hit_object.start_time = duration - end_time(hit_object);
+    }
+    reverse_points(&mut snippet.timing_points, duration);
+    reverse_points(&mut snippet.difficulty_points, duration);
+    reverse_points(&mut snippet.sample_points, duration);
+}
+
+fn holds_to_rice(snippet: &mut Snippets) {
+    for hit_object in snippet.hit_objects.iter_mut() {
+        if let HitObjectKind::Hold(h) = &hit_object.kind {
+            let x = h.pos_x;
+            *hit_object = circle(hit_object.start_time, x, hit_object.samples.clone());
+        }
+    }
+}
+
+/// Transforme les notes simples en LN de `ln_length` ms, raccourcis si la note suivante
+/// de la colonne arrive avant.
+fn rice_to_holds(snippet: &mut Snippets, ln_length: f64) {
+    let keycount = snippet.keycount;
+    let starts: Vec<(Option<usize>, f64)> = snippet
+        .hit_objects
+        .iter()
+        .map(|h| (columns::column_of(h, keycount), h.start_time))
+        .collect();
+
+    for hit_object in snippet.hit_objects.iter_mut() {
+        let HitObjectKind::Circle(c) = &hit_object.kind else {
+            continue;
+        };
+        let x = c.pos.x;
+        let column = columns::column_of(hit_object, keycount);
+        let next = starts
+            .iter()
+            .filter(|(col, time)| *col == column && *time > hit_object.start_time)
+            .map(|(_, time)| *time)
+            .fold(f64::MAX, f64::min);
+        // On laisse un petit trou avant la note suivante pour que le release soit jouable
+        let duration = ln_length.min(next - hit_object.start_time - 30.0);
+        if duration <= 0.0 {
+            continue;
+        }
+        hit_object.kind = HitObjectKind::Hold(HitObjectHold {
+            pos_x: x,
+            duration,
+        });
+    }
+}
+
+/// Garde un accord sur deux.
+fn halve_density(snippet: &mut Snippets) {
+    let keep: Vec<usize> = chords(&snippet.hit_objects)
+        .into_iter()
+        .step_by(2)
+        .flatten()
+        .collect();
+    let mut idx = 0;
+    snippet.hit_objects.retain(|_| {
+        let kept = keep.contains(&idx);
+        idx += 1;
+        kept
+    });
+}
+
+/// Un LN de la colonne est tenu à `time`, ou vient juste d'être relâché.
+fn is_held(hit_objects: &[HitObject], column: usize, time: f64, keycount: usize) -> bool {
+    hit_objects.iter().any(|h| match &h.kind {
+        HitObjectKind::Hold(hold) => {
+            columns::column_of(h, keycount) == Some(column)
+                && h.start_time <= time
+                && time < h.start_time + hold.duration + RELEASE_GAP
+        }
+        _ => false,
+    })
+}
+
+/// Ajoute un accord entre chaque paire d'accords, sur les colonnes miroir du précédent.
+/// Une colonne miroir occupée par un LN est remplacée par la colonne libre la plus proche,
+/// la note est abandonnée s'il n'y en a aucune.
+fn double_density(snippet: &mut Snippets) {
+    let keycount = snippet.keycount;
+    let chords = chords(&snippet.hit_objects);
+    let mut added = Vec::new();
+    for pair in chords.windows(2) {
+        let time = (snippet.hit_objects[pair[0][0]].start_time + snippet.hit_objects[pair[1][0]].start_time) / 2.0;
+        let mut used: Vec<usize> = Vec::new();
+        for &idx in &pair[0] {
+            let Some(column) = columns::column_of(&snippet.hit_objects[idx], keycount) else {
+                continue;
+            };
+            let mirror = keycount - 1 - column;
+            let mut candidates: Vec<usize> = (0..keycount).collect();
+            candidates.sort_by_key(|&c| (c as i64 - mirror as i64).abs());
+            let free = candidates
+                .into_iter()
+                .find(|&c| !used.contains(&c) && !is_held(&snippet.hit_objects, c, time, keycount));
+            let Some(target) = free else {
+                continue;
+            };
+            used.push(target);
+            added.push(circle(time, columns::column_x(target, keycount), snippet.hit_objects[idx].samples.clone()));
+        }
+    }
+    snippet.hit_objects.extend(added);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(time: f64, column: usize) -> HitObject {
+        circle(time, columns::column_x(column, 4), Vec::new())
+    }
+
+    fn hold(time: f64, duration: f64, column: usize) -> HitObject {
+        HitObject {
+            start_time: time,
+            kind: HitObjectKind::Hold(HitObjectHold {
+                pos_x: columns::column_x(column, 4),
+                duration,
+            }),
+            samples: Vec::new(),
+        }
+    }
+
+    fn snippet(hit_objects: Vec<HitObject>) -> Snippets {
+        let mut snippet = Snippets::new();
+        snippet.name = "test".to_string();
+        snippet.keycount = 4;
+        snippet.hit_objects = hit_objects;
+        snippet
+    }
+
+    /// (temps, colonne, durée du LN)
+    fn layout(snippet: &Snippets) -> Vec<(f64, usize, Option<f64>)> {
+        snippet
+            .hit_objects
+            .iter()
+            .map(|h| {
+                let duration = match &h.kind {
+                    HitObjectKind::Hold(hold) => Some(hold.duration),
+                    _ => None,
+                };
+                (h.start_time, columns::column_of(h, 4).unwrap(), duration)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stretch_scales_times_and_lengths() {
+        let result = RhythmTransform::Stretch(2.0).apply(&snippet(vec![note(0.0, 0), hold(100.0, 150.0, 1)]));
+        assert_eq!(layout(&result), [(0.0, 0, None), (200.0, 1, Some(300.0))]);
+        assert_eq!(result.name, "test (stretched x2.00)");
+        assert!(!result.is_saved);
+    }
+
+    #[test]
+    fn reverse_turns_hold_tails_into_heads() {
+        let result = RhythmTransform::Reverse.apply(&snippet(vec![note(0.0, 0), hold(100.0, 200.0, 1), note(400.0, 2)]));
+        assert_eq!(layout(&result), [(0.0, 2, None), (100.0, 1, Some(200.0)), (400.0, 0, None)]);
+    }
+
+    #[test]
+    fn reverse_twice_is_identity() {
+        let original = snippet(vec![note(0.0, 0), hold(50.0, 300.0, 3), note(200.0, 1), note(500.0, 2)]);
+        let twice = RhythmTransform::Reverse.apply(&RhythmTransform::Reverse.apply(&original));
+        assert_eq!(layout(&twice), layout(&original));
+    }
+
+    #[test]
+    fn halve_density_keeps_every_other_chord() {
+        let original = snippet(vec![note(0.0, 0), note(0.0, 3), note(100.0, 1), note(200.0, 2), note(300.0, 3)]);
+        let result = RhythmTransform::HalveDensity.apply(&original);
+        assert_eq!(layout(&result), [(0.0, 0, None), (0.0, 3, None), (200.0, 2, None)]);
+    }
+
+    #[test]
+    fn double_density_adds_mirrored_midpoints() {
+        let result = RhythmTransform::DoubleDensity.apply(&snippet(vec![note(0.0, 0), note(100.0, 1), note(200.0, 2)]));
+        assert_eq!(
+            layout(&result),
+            [(0.0, 0, None), (50.0, 3, None), (100.0, 1, None), (150.0, 2, None), (200.0, 2, None)]
+        );
+    }
+
+    #[test]
+    fn double_density_moves_notes_out_of_holds() {
+        // Le miroir de la colonne 3 est la colonne 0, tenue par le LN à 100ms
+        let result = RhythmTransform::DoubleDensity.apply(&snippet(vec![hold(0.0, 400.0, 0), note(0.0, 3), note(200.0, 2)]));
+        let added: Vec<(f64, usize, Option<f64>)> = layout(&result).into_iter().filter(|(t, _, _)| *t == 100.0).collect();
+        assert_eq!(added, [(100.0, 3, None), (100.0, 1, None)]);
+    }
+
+    #[test]
+    fn holds_to_rice_keeps_heads() {
+        let result = RhythmTransform::HoldsToRice.apply(&snippet(vec![hold(0.0, 300.0, 2), note(100.0, 1)]));
+        assert_eq!(layout(&result), [(0.0, 2, None), (100.0, 1, None)]);
+    }
+
+    #[test]
+    fn rice_to_holds_stops_before_the_next_note() {
+        let result = RhythmTransform::RiceToHolds(200.0).apply(&snippet(vec![note(0.0, 0), note(100.0, 0), note(100.0, 1)]));
+        assert_eq!(layout(&result), [(0.0, 0, Some(70.0)), (100.0, 0, Some(200.0)), (100.0, 1, Some(200.0))]);
+    }
+}
@@ -0,0 +1,226 @@
+use rosu_map::section::hit_objects::HitObjectKind;
+use crate::snippets::index::IndexEntry;
+use crate::snippets::structs::Snippets;
+use crate::snippets::tags;
+
+/// Champs sur lesquels on cherche, communs aux snippets chargées et à l'index.
+pub struct SearchFields<'a> {
+    pub name: &'a str,
+    pub tags: &'a [String],
+    pub source: String,
+    pub keycount: usize,
+    pub bpm: f64,
+    /// ms
+    pub duration: f64,
+    pub object_count: usize,
+    pub has_holds: bool,
+}
+
+impl<'a> SearchFields<'a> {
+    pub fn from_snippet(snippet: &'a Snippets) -> Self {
+        Self {
+            name: &snippet.name,
+            tags: &snippet.tags,
+            source: if snippet.source.is_known() { snippet.source.label() } else { String::new() },
+            keycount: snippet.keycount,
+            bpm: 60000.0 / snippet.base_beat_len(),
+            duration: snippet.duration(),
+            object_count: snippet.hit_objects.len(),
+            has_holds: snippet.hit_objects.iter().any(|h| matches!(h.kind, HitObjectKind::Hold(_))),
+        }
+    }
+
+    pub fn from_entry(entry: &'a IndexEntry) -> Self {
+        Self {
+            name: &entry.name,
+            tags: &entry.tags,
+            source: entry.source.clone(),
+            keycount: entry.keycount,
+            bpm: entry.bpm,
+            duration: entry.duration,
+            object_count: entry.object_count,
+            has_holds: entry.has_holds,
+        }
+    }
+
+    /// Notes par seconde
+    pub fn density(&self) -> f64 {
+        if self.duration <= 0.0 {
+            return 0.0;
+        }
+        self.object_count as f64 / (self.duration / 1000.0)
+    }
+}
+
+/// Intervalle inclusif, une borne absente veut dire pas de limite.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Range {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Range {
+    /// `150-200`, `>180`, `<10`, `150-` ou une valeur seule.
+    fn parse(text: &str) -> Option<Self> {
+        let number = |s: &str| -> Option<Option<f64>> {
+            if s.is_empty() { Some(None) } else { s.parse().ok().map(Some) }
+        };
+        if let Some(min) = text.strip_prefix('>') {
+            return Some(Self { min: Some(min.parse().ok()?), max: None });
+        }
+        if let Some(max) = text.strip_prefix('<') {
+            return Some(Self { min: None, max: Some(max.parse().ok()?) });
+        }
+        match text.split_once('-') {
+            Some((min, max)) => Some(Self { min: number(min)?, max: number(max)? }),
+            None => {
+                let value = text.parse().ok()?;
+                Some(Self { min: Some(value), max: Some(value) })
+            }
+        }
+    }
+
+    fn contains(&self, value: f64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+/// Requête de la barre de recherche. Syntaxe :
+/// `k:4 bpm:170-190 dur:<20 nps:>12 ln:yes tag:js tag:hs|chordjack -tag:ln texte libre`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchQuery {
+    pub terms: Vec<String>,
+    pub keycount: Option<usize>,
+    pub bpm: Range,
+    /// secondes
+    pub duration: Range,
+    /// notes par seconde
+    pub density: Range,
+    pub has_holds: Option<bool>,
+    /// Chaque groupe doit matcher (AND), un groupe matche si l'un de ses tags est présent (OR)
+    pub tag_groups: Vec<Vec<String>>,
+    pub excluded_tags: Vec<String>,
+}
+
+impl SearchQuery {
+    pub fn parse(text: &str) -> Self {
+        let mut query = Self::default();
+        for token in text.split_whitespace() {
+            let lower = token.to_lowercase();
+            let (negated, body) = match lower.strip_prefix('-') {
+                Some(body) => (true, body),
+                None => (false, lower.as_str()),
+            };
+            let parsed = match body.split_once(':') {
+                Some(("tag", tags)) if negated => {
+                    query.excluded_tags.extend(tags.split('|').map(str::to_string));
+                    true
+                }
+                Some(("tag", tags)) => {
+                    query.tag_groups.push(tags.split('|').map(str::to_string).collect());
+                    true
+                }
+                Some(("k" | "keys", value)) if !negated => value
+                    .trim_end_matches('k')
+                    .parse()
+                    .map(|k| query.keycount = Some(k))
+                    .is_ok(),
+                Some(("bpm", value)) if !negated => Range::parse(value).map(|r| query.bpm = r).is_some(),
+                Some(("dur", value)) if !negated => Range::parse(value).map(|r| query.duration = r).is_some(),
+                Some(("nps", value)) if !negated => Range::parse(value).map(|r| query.density = r).is_some(),
+                Some(("ln", value)) if !negated => match value {
+                    "yes" | "true" | "1" => {
+                        query.has_holds = Some(true);
+                        true
+                    }
+                    "no" | "false" | "0" => {
+                        query.has_holds = Some(false);
+                        true
+                    }
+                    _ => false,
+                },
+                _ => false,
+            };
+            // Un filtre mal écrit est cherché comme du texte
+            if !parsed {
+                query.terms.push(lower);
+            }
+        }
+        query
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn matches_filters(&self, fields: &SearchFields) -> bool {
+        // `tag:ln` trouve aussi les snippets taguées `ln/inverse`
+        let has_tag = |tag: &str| fields.tags.iter().any(|t| tags::tag_matches(t, tag));
+        self.keycount.is_none_or(|k| k == fields.keycount)
+            && self.bpm.contains(fields.bpm)
+            && self.duration.contains(fields.duration / 1000.0)
+            && self.density.contains(fields.density())
+            && self.has_holds.is_none_or(|h| h == fields.has_holds)
+            && self.tag_groups.iter().all(|group| group.iter().any(|t| has_tag(t)))
+            && !self.excluded_tags.iter().any(|t| has_tag(t))
+    }
+
+    /// None si la snippet ne passe pas les filtres, sinon un score (plus haut = plus pertinent).
+    /// Chaque terme doit apparaître quelque part ; le nom compte plus que les tags, puis la source.
+    pub fn score(&self, fields: &SearchFields) -> Option<f64> {
+        if !self.matches_filters(fields) {
+            return None;
+        }
+        let name = fields.name.to_lowercase();
+        let source = fields.source.to_lowercase();
+        let mut score = 1.0;
+        for term in &self.terms {
+            let term_score = if name == *term || name.trim_end_matches(".snippets") == term {
+                10.0
+            } else if name.starts_with(term.as_str()) {
+                6.0
+            } else if name.contains(term.as_str()) {
+                4.0
+            } else if fields.tags.iter().any(|t| t.to_lowercase() == *term) {
+                5.0
+            } else if fields.tags.iter().any(|t| t.to_lowercase().contains(term.as_str())) {
+                3.0
+            } else if source.contains(term.as_str()) {
+                2.0
+            } else {
+                return None;
+            };
+            score += term_score;
+        }
+        Some(score)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchHit {
+    /// Index dans `AppState::snippets`
+    Loaded(usize),
+    /// Index dans `AppState::unloaded_snippets`
+    Unloaded(usize),
+}
+
+/// Cherche dans les snippets chargées et dans l'index, triées par score.
+/// Un fichier déjà chargé n'apparaît qu'une fois, sous sa version chargée.
+pub fn search(query: &SearchQuery, loaded: &[Snippets], unloaded: &[IndexEntry]) -> Vec<(SearchHit, f64)> {
+    let mut hits: Vec<(SearchHit, f64)> = Vec::new();
+    for (idx, snippet) in loaded.iter().enumerate() {
+        if let Some(score) = query.score(&SearchFields::from_snippet(snippet)) {
+            hits.push((SearchHit::Loaded(idx), score));
+        }
+    }
+    for (idx, entry) in unloaded.iter().enumerate() {
+        if loaded.iter().any(|s| s.file_path.as_ref() == Some(&entry.path)) {
+            continue;
+        }
+        if let Some(score) = query.score(&SearchFields::from_entry(entry)) {
+            hits.push((SearchHit::Unloaded(idx), score));
+        }
+    }
+    hits.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    hits
+}
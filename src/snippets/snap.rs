@@ -0,0 +1,138 @@
+use rosu_map::section::hit_objects::{HitObject, HitObjectKind};
+use rosu_map::section::timing_points::TimingPoint;
+use crate::snippets::timing;
+
+/// Résumé de ce que le snapping a déplacé.
+#[derive(Clone, Debug, Default)]
+pub struct SnapReport {
+    pub moved: usize,
+    pub max_shift: f64,
+    /// Holds dont le début et la fin tombaient sur le même tick, rallongés d'un tick
+    pub lengthened: usize,
+}
+
+impl SnapReport {
+    fn record(&mut self, shift: f64) {
+        // En dessous d'1ms c'est juste de l'arrondi, ça ne compte pas comme déplacé
+        if shift.abs() >= 1.0 {
+            self.moved += 1;
+        }
+        self.max_shift = self.max_shift.max(shift.abs());
+    }
+}
+
+/// Tick le plus proche parmi les grilles de `SNAP_DIVISORS` jusqu'à 1/`max_divisor`,
+/// pour qu'un rythme en 1/3 ou 1/6 ne soit pas ramené sur du 1/4.
+/// À égalité, la grille la plus grossière l'emporte.
+fn snap(points: &[TimingPoint], time: f64, max_divisor: u32) -> f64 {
+    let Some(point) = timing::point_at(points, time) else {
+        return time;
+    };
+    let mut best = time;
+    let mut best_shift = f64::MAX;
+    for divisor in timing::SNAP_DIVISORS.into_iter().filter(|d| *d <= max_divisor.max(1)) {
+        let snapped = timing::snap_time(point, time, divisor);
+        if (snapped - time).abs() < best_shift - 1e-6 {
+            best = snapped;
+            best_shift = (snapped - time).abs();
+        }
+    }
+    best
+}
+
+/// Écart entre deux ticks 1/`divisor` à `time`.
+fn grid_step(points: &[TimingPoint], time: f64, divisor: u32) -> Option<f64> {
+    timing::point_at(points, time)
+        .map(|point| point.beat_len / divisor.max(1) as f64)
+        .filter(|step| *step > 0.0)
+}
+
+/// Quantifie le début et la fin des holds sur les grilles de la map cible jusqu'à 1/`divisor`.
+/// Deux notes peuvent tomber sur le même tick : `keycount::dedupe` doit passer après.
+pub fn snap_hit_objects(hit_objects: &mut [HitObject], points: &[TimingPoint], divisor: u32) -> SnapReport {
+    let mut report = SnapReport::default();
+    for hit_object in hit_objects.iter_mut() {
+        let start = hit_object.start_time;
+        let snapped_start = snap(points, start, divisor);
+        let mut shift = snapped_start - start;
+        hit_object.start_time = snapped_start;
+
+        if let HitObjectKind::Hold(ref mut hold) = hit_object.kind {
+            let end = start + hold.duration;
+            let mut snapped_end = snap(points, end, divisor);
+            // Un hold de 0ms n'est pas un objet valide, on lui laisse au moins un tick
+            if snapped_end <= snapped_start {
+                if let Some(step) = grid_step(points, snapped_start, divisor) {
+                    snapped_end = snapped_start + step;
+                    report.lengthened += 1;
+                }
+            }
+            hold.duration = (snapped_end - snapped_start).max(1.0);
+            if (snapped_end - end).abs() > shift.abs() {
+                shift = snapped_end - end;
+            }
+        }
+        report.record(shift);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosu_map::{Beatmap, DecodeBeatmap};
+    use rosu_map::section::hit_objects::{HitObjectCircle, HitObjectHold};
+    use rosu_map::util::Pos;
+
+    /// Un point rouge à 180 bpm
+    fn points() -> Vec<TimingPoint> {
+        let osu = "osu file format v14\n\n[TimingPoints]\n0,333.333333333333,4,1,0,100,1,0\n";
+        Beatmap::decode(osu.as_bytes()).unwrap().control_points.timing_points
+    }
+
+    fn note(time: f64) -> HitObject {
+        HitObject {
+            start_time: time,
+            kind: HitObjectKind::Circle(HitObjectCircle {
+                pos: Pos::new(64.0, 192.0),
+                new_combo: false,
+                combo_offset: 0,
+            }),
+            samples: Vec::new(),
+        }
+    }
+
+    fn times(hit_objects: &[HitObject]) -> Vec<f64> {
+        hit_objects.iter().map(|h| (h.start_time * 10.0).round() / 10.0).collect()
+    }
+
+    #[test]
+    fn sixths_stay_on_their_own_grid() {
+        let mut hit_objects = vec![note(0.0), note(55.8), note(110.9), note(167.0)];
+        let report = snap_hit_objects(&mut hit_objects, &points(), 16);
+        assert_eq!(times(&hit_objects), [0.0, 55.6, 111.1, 166.7]);
+        assert!(report.max_shift < 1.0);
+    }
+
+    #[test]
+    fn thirds_survive_a_quarter_limit() {
+        let mut hit_objects = vec![note(0.0), note(111.5), note(222.0)];
+        snap_hit_objects(&mut hit_objects, &points(), 4);
+        assert_eq!(times(&hit_objects), [0.0, 111.1, 222.2]);
+    }
+
+    #[test]
+    fn zero_length_holds_get_one_tick() {
+        let mut hit_objects = vec![HitObject {
+            start_time: 0.0,
+            kind: HitObjectKind::Hold(HitObjectHold { pos_x: 64.0, duration: 5.0 }),
+            samples: Vec::new(),
+        }];
+        let report = snap_hit_objects(&mut hit_objects, &points(), 4);
+        assert_eq!(report.lengthened, 1);
+        let HitObjectKind::Hold(hold) = &hit_objects[0].kind else {
+            panic!("expected a hold");
+        };
+        assert!((hold.duration - 333.333333333333 / 4.0).abs() < 1e-6);
+    }
+}
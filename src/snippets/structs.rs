@@ -1,25 +1,90 @@
 use eyre::Result;
-use rosu_mem::process::{Process};
-use rosu_memory_lib::reader::structs::{State};
-use rosu_memory_lib::reader::gameplay::stable::memory::get_ig_time;
-use rosu_map::section::timing_points::{TimingPoint};
+use rosu_map::section::timing_points::{DifficultyPoint, SamplePoint, TimingPoint};
 use rosu_map::section::hit_objects::{HitObject};
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
 use rosu_map::{Beatmap, DecodeBeatmap};
-use rosu_memory_lib::reader::beatmap::stable::file::get_beatmap_path;
 use rosu_map::section::hit_objects::HitObjectKind;
-use rand::seq::SliceRandom;
-use rand::Rng;
+use rosu_map::section::general::GameMode;
+use crate::snippets::history::{InsertHistory, InsertRecord};
+use crate::source::GameSource;
+use crate::snippets::timing;
+use crate::snippets::snap::{self, SnapReport};
+use crate::snippets::conflict::{self, ConflictMode, ConflictReport};
+use crate::snippets::keycount::{self, KeycountStrategy};
+use crate::snippets::columns;
+use crate::snippets::transform::{self, ColumnTransform};
+use crate::snippets::hitsound::{self, HitsoundMode};
+use crate::snippets::format;
+use crate::snippets::index;
+use crate::utils;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// D'où vient la snippet : map et portion capturée.
+#[derive(Clone, Debug, Default)]
+pub struct SnippetSource {
+    pub artist: String,
+    pub title: String,
+    pub version: String,
+    pub creator: String,
+    pub beatmap_id: i32,
+    pub beatmapset_id: i32,
+    pub start: i32,
+    pub end: i32,
+    /// Bpm de la map au début de la capture
+    pub bpm: f64,
+}
+
+impl SnippetSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Les snippets collées ou migrées n'ont pas de map d'origine.
+    pub fn is_known(&self) -> bool {
+        self.beatmap_id != 0 || !self.title.is_empty()
+    }
+
+    /// `Artist - Title [Version] (Creator)` comme dans osu!.
+    pub fn label(&self) -> String {
+        let mut label = format!("{} - {} [{}]", self.artist, self.title, self.version);
+        if !self.creator.is_empty() {
+            label.push_str(&format!(" ({})", self.creator));
+        }
+        label
+    }
+
+    /// Début de la capture au format des timestamps de l'éditeur, ex: `01:23:456`.
+    pub fn timestamp(&self) -> String {
+        let start = self.start.max(0);
+        format!("{:02}:{:02}:{:03}", start / 60000, start / 1000 % 60, start % 1000)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Snippets {
     pub name: String,
     pub hit_objects: Vec<HitObject>,
-    pub timing_points: TimingPoint,
+    pub timing_points: Vec<TimingPoint>,
+    pub difficulty_points: Vec<DifficultyPoint>,
+    pub sample_points: Vec<SamplePoint>,
     pub is_saved: bool,
-    pub should_shuffle: bool,  // Nouvelle option pour le shuffle
+    /// Seed du shuffle des colonnes, None si le shuffle est désactivé
+    pub shuffle_seed: Option<u64>,
     pub keycount: usize, // mania only 
     pub tags: Vec<String>,
+    pub transforms: Vec<ColumnTransform>,
+    pub author: String,
+    pub notes: String,
+    pub source: SnippetSource,
+    /// Timestamps unix (s), 0 tant que la snippet n'a jamais été sauvegardée
+    pub created_at: u64,
+    pub modified_at: u64,
+    /// Fichier d'où vient la snippet, None tant qu'elle n'a pas été sauvegardée
+    pub file_path: Option<PathBuf>,
+    /// mtime du fichier au dernier chargement/sauvegarde
+    pub disk_mtime: u64,
+    /// Le fichier a changé sur le disque depuis qu'on l'a chargé
+    pub stale: bool,
 }
 
 impl Snippets {
@@ -27,71 +92,125 @@ impl Snippets {
         Self {
             name: String::new(),
             hit_objects: Vec::new(),
-            timing_points: TimingPoint::default(),
+            timing_points: Vec::new(),
+            difficulty_points: Vec::new(),
+            sample_points: Vec::new(),
             is_saved: false,
-            should_shuffle: false,
+            shuffle_seed: None,
             keycount: 4,
             tags: Vec::new(),
+            transforms: Vec::new(),
+            author: String::new(),
+            notes: String::new(),
+            source: SnippetSource::new(),
+            created_at: 0,
+            modified_at: 0,
+            file_path: None,
+            disk_mtime: 0,
+            stale: false,
         }
     }
 
-    /// TODO: Supporter plus que 4 colonnes en récupérant le nombre de colonnes depuis la beatmap
-    fn shuffle_column(&self, column: usize) -> usize {
-        // Pour l'instant on hardcode 4 colonnes
-        let mut columns: Vec<usize> = (0..self.keycount).collect();
-        columns.shuffle(&mut rand::rng());
-        columns[column]
-    }
-
-    pub fn load_snippets_memory_path(&mut self, process: &Process, state: &mut State, snippets_maker: &SnippetsMaker) -> Result<()> {
-        println!("Loading snippets from memory path");
-        let beatmap_path = get_beatmap_path(process, state)?;
+    pub fn load_snippets_from_source(&mut self, source: &dyn GameSource, snippets_maker: &SnippetsMaker) -> Result<()> {
+        println!("Loading snippets from {}", source.name());
+        let mode = source.game_mode()?;
+        if mode != GameMode::Mania {
+            return Err(eyre::eyre!("Only osu!mania beatmaps are supported, got {:?}", mode));
+        }
+        let beatmap_path = source.beatmap_path()?;
         let beatmap = Beatmap::from_path(&beatmap_path)?;
-        println!("Beatmap loaded from memory path: {beatmap_path}");
+        println!("Beatmap loaded from {}: {}", source.name(), beatmap_path.display());
         self.load_snippets_from_beatmap(&beatmap, snippets_maker)
     }
 
-    pub fn load_snippets(&mut self, snippets_path: &str) -> Result<()> {
+    pub fn load_snippets(&mut self, snippets_path: &Path) -> Result<()> {
         println!("Loading snippets from file");
-        let snippets_path = std::path::Path::new("snippets").join(snippets_path);
-        let file = File::open(snippets_path)?;
-        let reader = BufReader::new(file);
-
-        // Decode snippets
-        let snippets = Beatmap::decode(reader)?;
-        self.name = snippets.title.clone();
-        self.hit_objects = snippets.hit_objects.clone();
-        self.timing_points = snippets.control_points.timing_points[0].clone();
-        self.tags = snippets.tags.split(" ").map(|s| s.to_string()).collect();
-        self.keycount = snippets.circle_size as usize;
-        self.is_saved = true;
+        let text = std::fs::read_to_string(snippets_path)?;
+        *self = format::read_any(&text)?;
+        self.file_path = Some(snippets_path.to_path_buf());
+        self.disk_mtime = index::modified_time(snippets_path);
         println!("Snippets loaded from file");
         Ok(())
     }
 
 
-    pub fn save_snippets_to_file(&mut self, snippets_name: &str) -> Result<()> {
-        println!("Saving snippets to file");
+    /// Sauvegarde dans `library_dir`, le nom de fichier devient le nom de la snippet.
+    pub fn save_snippets_to_file(&mut self, library_dir: &Path, snippets_name: &str) -> Result<()> {
         self.name = snippets_name.to_string();  // Mettre à jour le nom avec le nom du fichier
+        std::fs::create_dir_all(library_dir)?;
+        self.save_to_path(&library_dir.join(snippets_name))
+    }
 
-        let mut map = Beatmap::default();
-        map.title = self.name.clone();
-        map.hit_objects = self.hit_objects.clone();
-        map.tags = self.tags.clone().join(" ");
-        map.circle_size = self.keycount as f32;
-        let mut t_points = self.timing_points.clone();
-        t_points.time = 0.0;
-        map.control_points.timing_points = vec![t_points];
-        
-        let snippets_path = std::path::Path::new("snippets").join(snippets_name);
-        let file = File::create(snippets_path)?;
-        let writer = BufWriter::new(file);
-        map.encode(writer)?;
+    /// Réécrit la snippet à l'emplacement donné (bouton Update).
+    pub fn save_to_path(&mut self, snippets_path: &Path) -> Result<()> {
+        println!("Saving snippets to file");
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        if self.created_at == 0 {
+            self.created_at = now;
+        }
+        self.modified_at = now;
+
+        std::fs::write(snippets_path, format::write(self)?)?;
+        self.file_path = Some(snippets_path.to_path_buf());
+        self.disk_mtime = index::modified_time(snippets_path);
+        self.stale = false;
         self.is_saved = true;
         println!("Snippets saved to file");
         Ok(())
     }
 
+    /// Lignes `[HitObjects]` à coller dans l'éditeur d'osu!, temps relatifs au début de la snippet.
+    pub fn to_clipboard_text(&self) -> String {
+        let mut text = String::from("[HitObjects]\n");
+        for hit_object in &self.hit_objects {
+            text.push_str(&utils::hit_object_to_string(hit_object));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Crée une snippet depuis des lignes copiées dans l'éditeur d'osu!.
+    /// On les enrobe dans un .osu minimal pour réutiliser le décodeur de rosu-map.
+    /// Les lignes n'ont pas de timing : `bpm` est celui de la map d'où elles viennent.
+    pub fn from_hit_object_lines(text: &str, keycount: usize, bpm: f64) -> Result<Self> {
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('['))
+            .collect();
+        if lines.is_empty() {
+            return Err(eyre::eyre!("No hit object lines found"));
+        }
+        if bpm <= 0.0 {
+            return Err(eyre::eyre!("Invalid bpm: {}", bpm));
+        }
+        let osu = format!(
+            "osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nCircleSize:{}\n\n[TimingPoints]\n0,{},4,1,0,100,1,0\n\n[HitObjects]\n{}\n",
+            keycount,
+            60000.0 / bpm,
+            lines.join("\n")
+        );
+        let beatmap = Beatmap::decode(osu.as_bytes())?;
+        if beatmap.hit_objects.is_empty() {
+            return Err(eyre::eyre!("Could not parse any hit object"));
+        }
+
+        let mut snippet = Self::new();
+        snippet.keycount = keycount;
+        snippet.hit_objects = beatmap.hit_objects;
+        // Le point rouge est à 0, comme la première note une fois décalée
+        snippet.timing_points = beatmap.control_points.timing_points;
+        let start = snippet.hit_objects.iter().map(|h| h.start_time).fold(f64::MAX, f64::min);
+        for hit_object in snippet.hit_objects.iter_mut() {
+            hit_object.start_time -= start;
+            if let Some(column) = columns::column_of(hit_object, keycount) {
+                columns::set_column(hit_object, column, keycount);
+            }
+        }
+        snippet.hit_objects.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap());
+        Ok(snippet)
+    }
+
     pub fn collect_hit_objects(&mut self, beatmap: &Beatmap, snippets_maker: &SnippetsMaker) -> Result<()> {
         for hit_object in beatmap.hit_objects.clone() {
             if hit_object.start_time >= snippets_maker.time_start as f64 && hit_object.start_time <= snippets_maker.time_end as f64 {
@@ -102,36 +221,107 @@ impl Snippets {
     }
 
     pub fn collect_timing_points(&mut self, beatmap: &Beatmap, snippets_maker: &SnippetsMaker) -> Result<()> {
-        let mut closest_point = None;
-        let mut min_distance = f64::MAX;
-    
-        for timing_point in beatmap.control_points.timing_points.clone() {
-            if timing_point.time >= snippets_maker.time_start as f64 && timing_point.time <= snippets_maker.time_end as f64 {
-                self.timing_points = timing_point;
-                return Ok(());
-            }
+        let start = snippets_maker.time_start as f64;
+        let end = snippets_maker.time_end as f64;
+        // Tous les points rouges et verts de la sélection, plus ceux actifs au début
+        self.timing_points = timing::collect_range(&beatmap.control_points.timing_points, start, end);
+        self.difficulty_points = timing::collect_range(&beatmap.control_points.difficulty_points, start, end);
+        self.sample_points = timing::collect_range(&beatmap.control_points.sample_points, start, end);
+        Ok(())
+    }
 
-            let distance = (timing_point.time - snippets_maker.time_start as f64).abs();
-            if distance < min_distance {
-                min_distance = distance;
-                closest_point = Some(timing_point);
-            }
+    /// Les notes après application des transformations de colonnes, pour la preview et l'insertion.
+    pub fn transformed_hit_objects(&self) -> Vec<HitObject> {
+        let mut hit_objects = self.hit_objects.clone();
+        for t in &self.transforms {
+            transform::apply(&mut hit_objects, self.keycount, t);
         }
-        if closest_point.is_some() {
-            self.timing_points = closest_point.unwrap();
+        // Le shuffle passe en dernier, avec sa seed pour que la preview soit ce qui sera inséré
+        if let Some(seed) = self.shuffle_seed {
+            transform::apply(&mut hit_objects, self.keycount, &ColumnTransform::RandomSeeded(seed));
         }
-        Ok(())
+        hit_objects
+    }
+
+    /// beat_len du premier point rouge, celui qui sert de référence pour le scaling.
+    pub fn base_beat_len(&self) -> f64 {
+        self.timing_points.first().map(|t| t.beat_len).unwrap_or(600.0)
+    }
+
+    /// Durée du pattern, fin des holds comprise.
+    pub fn duration(&self) -> f64 {
+        self.hit_objects.iter().map(|h| match &h.kind {
+            HitObjectKind::Hold(hold) => h.start_time + hold.duration,
+            _ => h.start_time,
+        }).fold(0.0, f64::max)
+    }
+
+    /// Réinsère les points de la snippet dans la map, décalés et mis à l'échelle.
+    fn insert_control_points(&self, beatmap: &mut Beatmap, placement_time: f64, time_scale: f64) {
+        let end = placement_time + self.duration() * time_scale;
+
+        // Le premier point rouge correspond au bpm de la map, on ne réémet que les changements
+        if self.timing_points.len() > 1 {
+            let inserted = self.timing_points.iter().skip(1).map(|t| {
+                let mut t = t.clone();
+                t.time = t.time * time_scale + placement_time;
+                t.beat_len *= time_scale;
+                t
+            }).collect();
+            timing::splice(&mut beatmap.control_points.timing_points, inserted, placement_time, end, None);
+        }
+
+        if !self.difficulty_points.is_empty() {
+            let inserted = self.difficulty_points.iter().map(|d| {
+                let mut d = d.clone();
+                d.time = d.time * time_scale + placement_time;
+                d
+            }).collect();
+            timing::splice(&mut beatmap.control_points.difficulty_points, inserted, placement_time, end, Some(DifficultyPoint::default()));
+        }
+    }
+
+    /// Les points verts de la snippet, seulement quand on garde ses hitsounds.
+    fn insert_sample_points(&self, beatmap: &mut Beatmap, placement_time: f64, time_scale: f64) {
+        if self.sample_points.is_empty() {
+            return;
+        }
+        let end = placement_time + self.duration() * time_scale;
+        let inserted = self.sample_points.iter().map(|s| {
+            let mut s = s.clone();
+            s.time = s.time * time_scale + placement_time;
+            s
+        }).collect();
+        timing::splice(&mut beatmap.control_points.sample_points, inserted, placement_time, end, Some(SamplePoint::default()));
     }
-    
+
     pub fn load_snippets_from_beatmap(&mut self, beatmap: &Beatmap, snippets_maker: &SnippetsMaker) -> Result<()> {
         println!("Loading snippets from beatmap");
         self.collect_hit_objects(beatmap, snippets_maker)?;
         self.collect_timing_points(beatmap, snippets_maker)?;
         self.keycount = beatmap.circle_size as usize;
+        let start_beat_len = timing::point_at(&beatmap.control_points.timing_points, snippets_maker.time_start as f64)
+            .map(|t| t.beat_len)
+            .unwrap_or_default();
+        self.source = SnippetSource {
+            artist: beatmap.artist.clone(),
+            title: beatmap.title.clone(),
+            version: beatmap.version.clone(),
+            creator: beatmap.creator.clone(),
+            beatmap_id: beatmap.beatmap_id,
+            beatmapset_id: beatmap.beatmap_set_id,
+            start: snippets_maker.time_start,
+            end: snippets_maker.time_end,
+            bpm: if start_beat_len > 0.0 { 60000.0 / start_beat_len } else { 0.0 },
+        };
         
         // Normaliser les temps des notes par rapport au temps de début
+        // et les x au centre de leur colonne
         for hit_object in self.hit_objects.iter_mut() {
             hit_object.start_time -= snippets_maker.time_start as f64;
+            if let Some(column) = columns::column_of(hit_object, self.keycount) {
+                columns::set_column(hit_object, column, self.keycount);
+            }
         }
         
         println!("Snippets loaded from beatmap");
@@ -139,65 +329,208 @@ impl Snippets {
     }
 
 
-    pub fn insert_snippets_to_beatmap(&self, process: &Process, state: &mut State) -> Result<()> {
-        println!("Inserting snippets to beatmap");
-        let beatmap_path = get_beatmap_path(process, state)?;
-        let mut beatmap = Beatmap::from_path(&beatmap_path)?;
-        beatmap.tags = self.tags.clone().join(" ");
-        let current_beat_len = if let Some(last_timing) = beatmap.control_points.timing_points.last() {
-            last_timing.beat_len
-        } else {
-            600.0
-        };
+    pub fn insert_snippets_to_beatmap(&self, source: &dyn GameSource, history: &mut InsertHistory, options: &InsertOptions) -> Result<InsertReport> {
+        self.prepare_insert(source, options)?.commit(history, options.conflict_mode)
+    }
 
-        let time_scale = current_beat_len / self.timing_points.beat_len;
-        let placement_time = get_ig_time(process, state)?;
+    /// Calcule l'insertion sans toucher au fichier, pour pouvoir afficher les conflits avant.
+    pub fn prepare_insert(&self, source: &dyn GameSource, options: &InsertOptions) -> Result<PreparedInsert> {
+        println!("Preparing snippets insert");
+        let beatmap_path = source.beatmap_path()?;
+        let content = std::fs::read_to_string(&beatmap_path)?;
+        let mut beatmap = Beatmap::decode(content.as_bytes())?;
+        let mut placement_time = source.current_time()? as f64;
 
-        // Générer le mapping des colonnes une seule fois si shuffle est activé
-        let column_mapping: Option<Vec<usize>> = if self.should_shuffle {
-            let mut columns: Vec<usize> = (0..self.keycount).collect();
-            columns.shuffle(&mut rand::rng());
-            Some(columns)
+        // Le point rouge qui gouverne l'endroit où on insère, pas le dernier de la map
+        let current_timing = timing::point_at(&beatmap.control_points.timing_points, placement_time).cloned();
+        let current_beat_len = current_timing.as_ref().map(|t| t.beat_len).unwrap_or(600.0);
+        if let Some(current_timing) = current_timing.as_ref().filter(|_| options.snap_placement) {
+            placement_time = timing::snap_time(current_timing, placement_time, options.placement_divisor);
+        }
+
+        // Sans point rouge on ne sait pas à quel bpm la snippet a été faite, on ne la met pas à l'échelle
+        let time_scale = if self.timing_points.is_empty() {
+            1.0
         } else {
-            None
+            current_beat_len / self.base_beat_len()
         };
+        self.insert_control_points(&mut beatmap, placement_time, time_scale);
+        if options.hitsound_mode == HitsoundMode::Keep {
+            self.insert_sample_points(&mut beatmap, placement_time, time_scale);
+        }
 
-        for mut obj in self.hit_objects.clone() {
-            obj.start_time = obj.start_time * time_scale + placement_time as f64;
-            
-            if let HitObjectKind::Hold(ref mut h) = obj.kind {
-                h.duration = h.duration * time_scale;
-            }
-            // Appliquer le shuffle si activé
-            if self.should_shuffle {
-                if let Some(mapping) = &column_mapping {
-                    match obj.kind {
-                        HitObjectKind::Circle(ref mut h) => {
-                            let column = (h.pos.x / 512.0 * self.keycount as f32) as usize % self.keycount;
-                            let new_column = mapping[column];
-                            h.pos.x = (new_column as f32 * 512.0 / self.keycount as f32) + (512.0 / 8.0); // Centrer dans la colonne
-                        }
-                        HitObjectKind::Hold(ref mut h) => {
-                            let column = (h.pos_x / 512.0 * self.keycount as f32) as usize % self.keycount;
-                            let new_column = mapping[column];
-                            h.pos_x = (new_column as f32 * 512.0 / self.keycount as f32) + (512.0 / 8.0); // Centrer dans la colonne
-                        }
-                        _ => continue,
-                    }
+        // La snippet garde ses colonnes d'origine, on les convertit vers le keycount de la map cible
+        let target_keycount = (beatmap.circle_size as usize).max(1);
+        let mut inserted = Vec::with_capacity(self.hit_objects.len());
+        for hit_object in &self.transformed_hit_objects() {
+            let Some(column) = columns::column_of(hit_object, self.keycount) else {
+                continue;
+            };
+            for target_column in keycount::convert_column(column, self.keycount, target_keycount, options.keycount_strategy) {
+                let mut obj = hit_object.clone();
+                obj.start_time = obj.start_time * time_scale + placement_time;
+                if let HitObjectKind::Hold(ref mut h) = obj.kind {
+                    h.duration *= time_scale;
                 }
+                columns::set_column(&mut obj, target_column, target_keycount);
+                inserted.push(obj);
             }
+        }
+        hitsound::apply(&mut inserted, options.hitsound_mode);
+
+        // Après le scaling les temps tombent entre deux ticks, on les recale sur la grille de la map
+        let snap = if options.snap_objects {
+            snap::snap_hit_objects(&mut inserted, &beatmap.control_points.timing_points, options.object_divisor)
+        } else {
+            SnapReport::default()
+        };
+        // Fusion de colonnes et snapping peuvent empiler des notes ou en mettre dans un LN
+        let overlaps = keycount::dedupe(&mut inserted, target_keycount, options.min_gap);
 
-            beatmap.hit_objects.push(obj);
+        let region = (placement_time, placement_time + self.duration() * time_scale);
+        let conflicts = conflict::analyse(&beatmap.hit_objects, &inserted, target_keycount, options.min_gap);
+
+        Ok(PreparedInsert {
+            beatmap_path,
+            file_hash: utils::content_hash(&content),
+            beatmap,
+            inserted,
+            snippet_name: self.name.clone(),
+            shuffle_seed: self.shuffle_seed,
+            transforms: self.transforms.clone(),
+            placement_time,
+            region,
+            keycount: target_keycount,
+            source_keycount: self.keycount,
+            snap,
+            overlaps,
+            conflicts,
+        })
+    }
+}
+
+/// Une insertion calculée mais pas encore écrite dans le .osu.
+pub struct PreparedInsert {
+    pub beatmap_path: PathBuf,
+    /// Hash du .osu lu au moment du calcul, pour ne pas écraser des modifications faites depuis
+    pub file_hash: u64,
+    pub beatmap: Beatmap,
+    pub inserted: Vec<HitObject>,
+    pub snippet_name: String,
+    pub shuffle_seed: Option<u64>,
+    pub transforms: Vec<ColumnTransform>,
+    pub placement_time: f64,
+    pub region: (f64, f64),
+    pub keycount: usize,
+    pub source_keycount: usize,
+    pub snap: SnapReport,
+    /// Notes de la snippet qui se chevauchaient entre elles dans la map cible, retirées ou raccourcies
+    pub overlaps: usize,
+    pub conflicts: ConflictReport,
+}
+
+impl PreparedInsert {
+    pub fn commit(mut self, history: &mut InsertHistory, mode: ConflictMode) -> Result<InsertReport> {
+        println!("Inserting snippets to beatmap");
+        // Le dialogue peut rester ouvert pendant qu'on sauvegarde la map dans l'éditeur
+        if utils::content_hash(&std::fs::read_to_string(&self.beatmap_path)?) != self.file_hash {
+            return Err(eyre::eyre!(
+                "{} changed since the insert was prepared, insert again",
+                self.beatmap_path.display()
+            ));
         }
+        conflict::resolve(
+            &mut self.beatmap.hit_objects,
+            &mut self.inserted,
+            &self.conflicts,
+            mode,
+            self.keycount,
+            self.region,
+        )?;
+        let object_count = self.inserted.len();
+        self.beatmap.hit_objects.extend(self.inserted);
+        self.beatmap.hit_objects.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap());
 
-        beatmap.hit_objects.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap());
-        beatmap.encode_to_path(&beatmap_path)?;
+        // Sauvegarde du .osu original avant de le réécrire
+        let backup_path = history.snapshot(&self.beatmap_path)?;
+        self.beatmap.encode_to_path(&self.beatmap_path)?;
+        let record = history.push(InsertRecord {
+            id: 0,
+            created_at: 0,
+            beatmap_path: self.beatmap_path,
+            backup_path,
+            snippet_name: self.snippet_name,
+            placement_time: self.placement_time.round() as i32,
+            object_count,
+            shuffle_seed: self.shuffle_seed,
+            transforms: self.transforms,
+        })?;
         println!("Snippets inserted to beatmap");
-        Ok(())
+        Ok(InsertReport { record, snap: self.snap, overlaps: self.overlaps, conflicts: self.conflicts, mode })
     }
 }
 
 
+#[derive(Clone, Debug)]
+pub struct InsertOptions {
+    pub snap_placement: bool,
+    pub placement_divisor: u32,
+    pub snap_objects: bool,
+    pub object_divisor: u32,
+    pub conflict_mode: ConflictMode,
+    /// Écart minimum (ms) entre deux notes d'une même colonne
+    pub min_gap: f64,
+    pub keycount_strategy: KeycountStrategy,
+    pub hitsound_mode: HitsoundMode,
+}
+
+impl InsertOptions {
+    pub fn new() -> Self {
+        Self {
+            snap_placement: false,
+            placement_divisor: 4,
+            snap_objects: true,
+            // Toutes les grilles : le 1/3 et le 1/6 restent en place
+            object_divisor: 16,
+            conflict_mode: ConflictMode::Abort,
+            min_gap: 20.0,
+            keycount_strategy: KeycountStrategy::Center,
+            hitsound_mode: HitsoundMode::Keep,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct InsertReport {
+    pub record: InsertRecord,
+    pub snap: SnapReport,
+    pub overlaps: usize,
+    pub conflicts: ConflictReport,
+    pub mode: ConflictMode,
+}
+
+impl InsertReport {
+    pub fn summary(&self) -> String {
+        let mut summary = format!("Successfully inserted {}", self.record.snippet_name);
+        if self.snap.moved > 0 {
+            summary.push_str(&format!(
+                " ({} objects snapped, max {:.1}ms)",
+                self.snap.moved, self.snap.max_shift
+            ));
+        }
+        if self.snap.lengthened > 0 {
+            summary.push_str(&format!(", {} short holds lengthened", self.snap.lengthened));
+        }
+        if self.overlaps > 0 {
+            summary.push_str(&format!(", {} overlapping notes fixed", self.overlaps));
+        }
+        if !self.conflicts.is_empty() {
+            summary.push_str(&format!(", {} conflicts ({})", self.conflicts.conflicts.len(), self.mode.label()));
+        }
+        summary
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SnippetsMaker {
     pub time_start: i32,
@@ -238,12 +571,135 @@ impl SnippetsMaker {
         }
     }
 
-    pub fn set_next(&mut self, p: &Process, state: &mut State) -> Result<(NextUpdate), eyre::Error> 
+    pub fn set_start(&mut self, ig_time: i32) {
+        println!("Time start: {ig_time}");
+        self.time_start = ig_time;
+        self.next_update = NextUpdate::TimeEnd;
+    }
+
+    pub fn set_end(&mut self, ig_time: i32) {
+        println!("Time end: {ig_time}");
+        self.time_end = ig_time;
+        self.next_update = NextUpdate::TimeStart;
+    }
+
+    pub fn set_next(&mut self, source: &dyn GameSource) -> Result<(NextUpdate), eyre::Error> 
     {
-        if let Ok(ig_time) = get_ig_time(p, state) {
+        if let Ok(ig_time) = source.current_time() {
             self.set_ig_time(ig_time)
         } else {
             Err(eyre::eyre!("Failed to get ig time"))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::FileSource;
+    use std::fs;
+
+    /// Lignes écrites comme les réécrit l'éditeur : samples hérités, LN, sample explicite et fichier.
+    const CAPTURED: [&str; 5] = [
+        "64,192,1000,1,0,0:0:0:0:",
+        "192,192,1250,128,0,1750:0:0:0:0:",
+        "320,192,1500,1,2,0:0:0:0:",
+        "448,192,1500,128,8,2000:2:0:1:60:",
+        "64,192,1750,1,0,0:0:0:0:hit.wav",
+    ];
+
+    fn osu(sample_point: &str, hit_objects: &[&str]) -> String {
+        format!(
+            "osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nCircleSize:4\n\n[TimingPoints]\n0,500,4,1,0,70,1,0\n{}\n\n[HitObjects]\n{}\n",
+            sample_point,
+            hit_objects.join("\n"),
+        )
+    }
+
+    /// Lignes `[HitObjects]` du fichier dont le temps tombe dans [start, end].
+    fn object_lines(path: &Path, start: i32, end: i32) -> Vec<String> {
+        let text = fs::read_to_string(path).unwrap();
+        let objects = text.split("[HitObjects]").nth(1).unwrap_or_default();
+        objects
+            .lines()
+            .map(str::trim)
+            .take_while(|l| !l.starts_with('['))
+            .filter(|l| l.split(',').nth(2).and_then(|t| t.parse::<i32>().ok()).is_some_and(|t| t >= start && t <= end))
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn captured_objects_insert_byte_identically() {
+        let dir = std::env::temp_dir().join(format!("rosu-snippets-insert-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let source_path = dir.join("source.osu");
+        let target_path = dir.join("target.osu");
+        fs::write(&source_path, osu("0,-100,4,2,1,50,0,0", &CAPTURED)).unwrap();
+        // Point vert différent dans la cible : les samples hérités ne doivent pas être figés
+        fs::write(&target_path, osu("0,-100,4,1,0,100,0,0", &["64,192,10000,1,0,0:0:0:0:"])).unwrap();
+
+        let mut source = FileSource::new();
+        source.beatmap_path = source_path.display().to_string();
+        let maker = SnippetsMaker {
+            time_start: 1000,
+            time_end: 2000,
+            next_update: NextUpdate::TimeStart,
+        };
+        let mut captured = Snippets::new();
+        captured.load_snippets_from_source(&source, &maker).unwrap();
+        let snippet_path = dir.join("captured.snippets");
+        fs::write(&snippet_path, format::write(&captured).unwrap()).unwrap();
+        let mut reloaded = Snippets::new();
+        reloaded.load_snippets(&snippet_path).unwrap();
+
+        let mut target = FileSource::new();
+        target.beatmap_path = target_path.display().to_string();
+        target.time = 1000;
+        let options = InsertOptions::new();
+        let mut history = InsertHistory::in_dir(dir.join("backups"));
+        reloaded
+            .prepare_insert(&target, &options)
+            .and_then(|prepared| prepared.commit(&mut history, options.conflict_mode))
+            .unwrap();
+        let inserted = object_lines(&target_path, 1000, 2000);
+        let untouched = object_lines(&target_path, 10000, 10000);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(inserted, CAPTURED);
+        assert_eq!(untouched, ["64,192,10000,1,0,0:0:0:0:"]);
+    }
+
+    #[test]
+    fn stale_prepared_inserts_are_refused() {
+        let dir = std::env::temp_dir().join(format!("rosu-snippets-stale-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("map.osu");
+        fs::write(&path, osu("0,-100,4,1,0,100,0,0", &CAPTURED)).unwrap();
+
+        let mut source = FileSource::new();
+        source.beatmap_path = path.display().to_string();
+        let maker = SnippetsMaker {
+            time_start: 1000,
+            time_end: 2000,
+            next_update: NextUpdate::TimeStart,
+        };
+        let mut snippets = Snippets::new();
+        snippets.load_snippets_from_source(&source, &maker).unwrap();
+        source.time = 5000;
+        let prepared = snippets.prepare_insert(&source, &InsertOptions::new()).unwrap();
+        // La map est sauvegardée dans l'éditeur pendant que le dialogue est ouvert
+        fs::write(&path, osu("0,-100,4,1,0,100,0,0", &CAPTURED[..1])).unwrap();
+
+        let mut history = InsertHistory::in_dir(dir.join("backups"));
+        let result = prepared.commit(&mut history, ConflictMode::Abort);
+        let lines = object_lines(&path, 0, 10000);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(result.is_err());
+        assert_eq!(lines, ["64,192,1000,1,0,0:0:0:0:"]);
+        assert!(history.records.is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+use eyre::Result;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::snippets::format;
+use crate::snippets::index::IndexEntry;
+use crate::snippets::library::{self, LibraryRoot};
+
+/// Séparateur des tags hiérarchiques, ex: `ln/inverse`.
+pub const SEPARATOR: char = '/';
+
+/// `tag` est `filter` ou l'un de ses sous-tags (`ln` matche `ln/inverse`).
+pub fn tag_matches(tag: &str, filter: &str) -> bool {
+    tag.eq_ignore_ascii_case(filter)
+        || (tag.len() > filter.len()
+            && tag.as_bytes()[filter.len()] == SEPARATOR as u8
+            && tag[..filter.len()].eq_ignore_ascii_case(filter))
+}
+
+/// Un niveau de l'arbre des tags.
+#[derive(Clone, Debug, Default)]
+pub struct TagNode {
+    /// Chemin complet, `ln/inverse`
+    pub path: String,
+    /// Snippets qui ont ce tag ou un de ses sous-tags, comptées une fois
+    pub count: usize,
+    pub children: BTreeMap<String, TagNode>,
+}
+
+impl TagNode {
+    /// Dernier segment, ce qu'on affiche dans l'arbre.
+    pub fn name(&self) -> &str {
+        self.path.rsplit(SEPARATOR).next().unwrap_or(&self.path)
+    }
+
+    /// Chemins de tous les noeuds sous celui-ci, parents avant enfants.
+    pub fn paths(&self) -> Vec<&str> {
+        let mut paths = Vec::new();
+        for child in self.children.values() {
+            paths.push(child.path.as_str());
+            paths.extend(child.paths());
+        }
+        paths
+    }
+}
+
+/// Construit l'arbre à partir des tags de chaque snippet.
+pub fn build_tree<'a>(tag_lists: impl Iterator<Item = &'a [String]>) -> TagNode {
+    let mut root = TagNode::default();
+    for tags in tag_lists {
+        // Tous les préfixes des tags de la snippet, pour ne la compter qu'une fois par noeud
+        let mut paths: Vec<Vec<&str>> = Vec::new();
+        for tag in tags.iter().filter(|t| !t.is_empty()) {
+            let segments: Vec<&str> = tag.split(SEPARATOR).filter(|s| !s.is_empty()).collect();
+            for len in 1..=segments.len() {
+                let prefix = segments[..len].to_vec();
+                if !paths.contains(&prefix) {
+                    paths.push(prefix);
+                }
+            }
+        }
+        for segments in paths {
+            let mut node = &mut root;
+            for (depth, segment) in segments.iter().enumerate() {
+                node = node.children.entry(segment.to_string()).or_insert_with(|| TagNode {
+                    path: segments[..=depth].join("/"),
+                    ..TagNode::default()
+                });
+            }
+            node.count += 1;
+        }
+    }
+    root
+}
+
+/// Renomme `from` (et ses sous-tags) en `to` dans une liste de tags.
+/// Si `to` existe déjà les deux sont fusionnés. Renvoie true si la liste a changé.
+pub fn rename_tag(tags: &mut Vec<String>, from: &str, to: &str) -> bool {
+    let mut changed = false;
+    let mut renamed: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags.iter() {
+        let new_tag = if tag_matches(tag, from) {
+            changed = true;
+            format!("{}{}", to, &tag[from.len()..])
+        } else {
+            tag.clone()
+        };
+        if !new_tag.is_empty() && !renamed.contains(&new_tag) {
+            renamed.push(new_tag);
+        }
+    }
+    if changed {
+        *tags = renamed;
+    }
+    changed
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RenameReport {
+    pub rewritten: Vec<PathBuf>,
+    pub read_only: Vec<PathBuf>,
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl RenameReport {
+    pub fn summary(&self, from: &str, to: &str) -> String {
+        let mut summary = format!("Renamed {} to {} in {} files", from, to, self.rewritten.len());
+        if !self.read_only.is_empty() {
+            summary.push_str(&format!(", {} read-only skipped", self.read_only.len()));
+        }
+        if !self.failed.is_empty() {
+            summary.push_str(&format!(", {} failed", self.failed.len()));
+        }
+        summary
+    }
+}
+
+fn rename_in_file(path: &Path, from: &str, to: &str) -> Result<bool> {
+    let mut snippet = format::read_any(&fs::read_to_string(path)?)?;
+    if !rename_tag(&mut snippet.tags, from, to) {
+        return Ok(false);
+    }
+    fs::write(path, format::write(&snippet)?)?;
+    Ok(true)
+}
+
+/// Réécrit tous les fichiers de la bibliothèque qui portent `from`.
+pub fn rename_in_library(entries: &[IndexEntry], roots: &[LibraryRoot], from: &str, to: &str) -> RenameReport {
+    let mut report = RenameReport::default();
+    for entry in entries.iter().filter(|e| e.tags.iter().any(|t| tag_matches(t, from))) {
+        if library::root_of(roots, &entry.path).is_some_and(|r| !r.kind.is_writable()) {
+            report.read_only.push(entry.path.clone());
+            continue;
+        }
+        match rename_in_file(&entry.path, from, to) {
+            Ok(true) => report.rewritten.push(entry.path.clone()),
+            Ok(false) => {}
+            Err(e) => report.failed.push((entry.path.clone(), e.to_string())),
+        }
+    }
+    report
+}
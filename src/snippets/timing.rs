@@ -0,0 +1,131 @@
+use rosu_map::section::timing_points::{DifficultyPoint, SamplePoint, TimingPoint};
+
+/// Diviseurs proposés dans l'UI, comme dans l'éditeur d'osu!.
+pub const SNAP_DIVISORS: [u32; 8] = [1, 2, 3, 4, 6, 8, 12, 16];
+
+/// Accès commun au temps des différents control points.
+pub trait Timed: Clone {
+    fn time(&self) -> f64;
+    fn set_time(&mut self, time: f64);
+
+    /// Point en vigueur avant le premier point de la map.
+    fn before_first(first: &Self) -> Self;
+
+    /// Où remettre ce point quand une insertion se termine à `end`.
+    fn restore_time(&self, end: f64) -> f64 {
+        end
+    }
+}
+
+impl Timed for TimingPoint {
+    fn time(&self) -> f64 {
+        self.time
+    }
+
+    fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    /// Avant le premier point rouge, osu! utilise déjà sa grille
+    fn before_first(first: &Self) -> Self {
+        first.clone()
+    }
+
+    /// Un point rouge remet la grille à zéro : on le replace sur un temps de sa propre grille
+    /// pour que les notes qui suivent restent snappées.
+    fn restore_time(&self, end: f64) -> f64 {
+        if self.beat_len <= 0.0 {
+            return end;
+        }
+        self.time + ((end - self.time) / self.beat_len).ceil() * self.beat_len
+    }
+}
+
+impl Timed for DifficultyPoint {
+    fn time(&self) -> f64 {
+        self.time
+    }
+
+    fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    fn before_first(_: &Self) -> Self {
+        DifficultyPoint::default()
+    }
+}
+
+impl Timed for SamplePoint {
+    fn time(&self) -> f64 {
+        self.time
+    }
+
+    fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    fn before_first(_: &Self) -> Self {
+        SamplePoint::default()
+    }
+}
+
+/// Point rouge actif à `time` : le dernier qui commence avant, sinon le premier.
+pub fn point_at(points: &[TimingPoint], time: f64) -> Option<&TimingPoint> {
+    points
+        .iter()
+        .take_while(|p| p.time <= time)
+        .last()
+        .or_else(|| points.first())
+}
+
+/// Point actif à `time`. Avant le premier point c'est `before_first` qui s'applique,
+/// un point vert ou SV plus loin dans la map n'est jamais ramené en arrière.
+fn active_at<T: Timed>(points: &[T], time: f64) -> Option<T> {
+    match points.iter().take_while(|p| p.time() <= time).last() {
+        Some(point) => Some(point.clone()),
+        None => points.first().map(T::before_first),
+    }
+}
+
+/// Récupère les points entre `start` et `end`, ramenés à 0.
+/// Le point actif au début est toujours inclus, placé à 0.
+pub fn collect_range<T: Timed>(points: &[T], start: f64, end: f64) -> Vec<T> {
+    let mut collected = Vec::new();
+    if let Some(mut active) = active_at(points, start) {
+        active.set_time(0.0);
+        collected.push(active);
+    }
+    for point in points.iter().filter(|p| p.time() > start && p.time() <= end) {
+        let mut point = point.clone();
+        point.set_time(point.time() - start);
+        collected.push(point);
+    }
+    collected
+}
+
+/// Remplace les points de `target` entre `start` et `end` par `inserted`,
+/// puis remet le point qui était actif à `end` pour ne pas changer la suite de la map.
+pub fn splice<T: Timed>(target: &mut Vec<T>, inserted: Vec<T>, start: f64, end: f64, fallback: Option<T>) {
+    let restore = active_at(target, end).or(fallback);
+    target.retain(|p| p.time() < start || p.time() >= end);
+    // Un point de la map entre `end` et le point remis reprend déjà la main
+    let restore = restore
+        .map(|mut p| {
+            p.set_time(p.restore_time(end));
+            p
+        })
+        .filter(|r| !target.iter().any(|p| p.time() >= end && p.time() <= r.time()));
+    target.extend(inserted);
+    // Poussé après `inserted` pour gagner sur un point de la snippet au même temps
+    target.extend(restore);
+    target.sort_by(|a, b| a.time().partial_cmp(&b.time()).unwrap());
+}
+
+/// Ramène `time` sur le tick 1/`divisor` le plus proche de la grille de `point`.
+pub fn snap_time(point: &TimingPoint, time: f64, divisor: u32) -> f64 {
+    let step = point.beat_len / divisor.max(1) as f64;
+    if step <= 0.0 {
+        return time;
+    }
+    point.time + ((time - point.time) / step).round() * step
+}
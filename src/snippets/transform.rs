@@ -0,0 +1,414 @@
+use rosu_map::section::hit_objects::{HitObject, HitObjectKind};
+use crate::snippets::columns;
+
+/// Écart laissé entre le relâchement d'un LN et la note suivante posée dans sa colonne
+pub(crate) const RELEASE_GAP: f64 = 20.0;
+
+/// Transformations de colonnes appliquées dans l'ordre avant l'insertion.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnTransform {
+    Mirror,
+    /// Décale toutes les notes de N colonnes (avec retour au début)
+    Rotate(i32),
+    /// `permutation[ancienne colonne] = nouvelle colonne`
+    Permutation(Vec<usize>),
+    RandomSeeded(u64),
+    /// Shuffle par accord qui évite de créer des jacks absents du pattern d'origine
+    NoJacks(u64),
+    /// Échange main gauche / main droite
+    HandSwap,
+}
+
+impl ColumnTransform {
+    pub fn label(&self) -> String {
+        match self {
+            ColumnTransform::Mirror => "Mirror".to_string(),
+            ColumnTransform::Rotate(n) => format!("Rotate {:+}", n),
+            ColumnTransform::Permutation(p) => {
+                let p: Vec<String> = p.iter().map(|c| (c + 1).to_string()).collect();
+                format!("Permutation {}", p.join(""))
+            }
+            ColumnTransform::RandomSeeded(seed) => format!("Random (seed {})", seed),
+            ColumnTransform::NoJacks(seed) => format!("No jacks (seed {})", seed),
+            ColumnTransform::HandSwap => "Hand swap".to_string(),
+        }
+    }
+
+    /// Parse une permutation écrite comme dans les mods osu!, ex: `2143` (colonnes à partir de 1).
+    pub fn parse_permutation(text: &str, keycount: usize) -> Option<Self> {
+        let permutation: Vec<usize> = text
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|p| !p.is_empty())
+            .flat_map(|p| {
+                if keycount < 10 && p.len() > 1 {
+                    p.chars().map(|c| c.to_digit(10).map(|d| d as usize)).collect::<Vec<_>>()
+                } else {
+                    vec![p.parse::<usize>().ok()]
+                }
+            })
+            .map(|c| c.and_then(|c| c.checked_sub(1)))
+            .collect::<Option<Vec<_>>>()?;
+        is_permutation(&permutation, keycount).then_some(ColumnTransform::Permutation(permutation))
+    }
+
+    /// Parse la forme écrite dans les fichiers, ex: `Rotate(-1)` ou `NoJacks(42)`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (name, arg) = match text.trim().split_once('(') {
+            Some((name, rest)) => (name, Some(rest.strip_suffix(')')?)),
+            None => (text.trim(), None),
+        };
+        let transform = match (name, arg) {
+            ("Mirror", None) => ColumnTransform::Mirror,
+            ("HandSwap", None) => ColumnTransform::HandSwap,
+            ("Rotate", Some(n)) => ColumnTransform::Rotate(n.parse().ok()?),
+            ("Random", Some(seed)) => ColumnTransform::RandomSeeded(seed.parse().ok()?),
+            ("NoJacks", Some(seed)) => ColumnTransform::NoJacks(seed.parse().ok()?),
+            ("Permutation", Some(p)) => {
+                ColumnTransform::Permutation(p.split(',').map(|c| c.trim().parse().ok()).collect::<Option<_>>()?)
+            }
+            _ => return None,
+        };
+        Some(transform)
+    }
+}
+
+/// Forme sans espace relue par `ColumnTransform::parse`, seeds comprises.
+impl std::fmt::Display for ColumnTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColumnTransform::Mirror => write!(f, "Mirror"),
+            ColumnTransform::Rotate(n) => write!(f, "Rotate({})", n),
+            ColumnTransform::Permutation(p) => {
+                let p: Vec<String> = p.iter().map(|c| c.to_string()).collect();
+                write!(f, "Permutation({})", p.join(","))
+            }
+            ColumnTransform::RandomSeeded(seed) => write!(f, "Random({})", seed),
+            ColumnTransform::NoJacks(seed) => write!(f, "NoJacks({})", seed),
+            ColumnTransform::HandSwap => write!(f, "HandSwap"),
+        }
+    }
+}
+
+fn is_permutation(permutation: &[usize], keycount: usize) -> bool {
+    let mut sorted = permutation.to_vec();
+    sorted.sort();
+    sorted == (0..keycount).collect::<Vec<_>>()
+}
+
+fn hand_swap(column: usize, keycount: usize) -> usize {
+    let half = keycount / 2;
+    if keycount.is_multiple_of(2) {
+        (column + half) % keycount
+    } else if column < half {
+        column + half + 1
+    } else if column > half {
+        column - half - 1
+    } else {
+        column
+    }
+}
+
+/// Mapping colonne -> colonne pour les transformations qui sont de simples permutations.
+fn column_mapping(transform: &ColumnTransform, keycount: usize) -> Option<Vec<usize>> {
+    let k = keycount as i32;
+    let mapping = match transform {
+        ColumnTransform::Mirror => (0..keycount).map(|c| keycount - 1 - c).collect(),
+        ColumnTransform::Rotate(n) => (0..k).map(|c| (c + n).rem_euclid(k) as usize).collect(),
+        ColumnTransform::Permutation(p) if is_permutation(p, keycount) => p.clone(),
+        ColumnTransform::Permutation(_) => (0..keycount).collect(),
+        ColumnTransform::RandomSeeded(seed) => seeded_permutation(*seed, keycount),
+        ColumnTransform::HandSwap => (0..keycount).map(|c| hand_swap(c, keycount)).collect(),
+        ColumnTransform::NoJacks(_) => return None,
+    };
+    Some(mapping)
+}
+
+/// SplitMix64. Les seeds sont sauvegardées avec les transformations (.snippets, history.txt) : il faut que
+/// la même seed donne le même shuffle partout et pour toujours, ce que `StdRng` ne garantit pas d'une version de rand à l'autre.
+pub(crate) struct SeededRng(u64);
+
+impl SeededRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Fisher-Yates
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+pub fn seeded_permutation(seed: u64, keycount: usize) -> Vec<usize> {
+    let mut columns: Vec<usize> = (0..keycount).collect();
+    SeededRng::new(seed).shuffle(&mut columns);
+    columns
+}
+
+/// Regroupe les notes par accord (même temps de début), dans l'ordre du pattern.
+pub fn chords(hit_objects: &[HitObject]) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..hit_objects.len()).collect();
+    order.sort_by(|&a, &b| hit_objects[a].start_time.partial_cmp(&hit_objects[b].start_time).unwrap());
+    let mut chords: Vec<Vec<usize>> = Vec::new();
+    for idx in order {
+        match chords.last_mut() {
+            Some(chord) if (hit_objects[chord[0]].start_time - hit_objects[idx].start_time).abs() < 1.0 => chord.push(idx),
+            _ => chords.push(vec![idx]),
+        }
+    }
+    chords
+}
+
+/// Quand aucun tirage ne tombe sur des colonnes libres : chaque note prend la colonne libérée
+/// le plus tôt, en évitant celles de l'accord précédent.
+fn fallback_mapping(from_columns: &[usize], free_at: &[f64], time: f64, previous_new: &[usize]) -> Vec<usize> {
+    let keycount = free_at.len();
+    let mut targets: Vec<usize> = (0..keycount).collect();
+    targets.sort_by(|&a, &b| {
+        let key = |c: usize| (free_at[c] > time, previous_new.contains(&c), free_at[c]);
+        key(a).partial_cmp(&key(b)).unwrap()
+    });
+    let mut mapping: Vec<usize> = (0..keycount).collect();
+    for (&from, &to) in from_columns.iter().zip(&targets) {
+        mapping[from] = to;
+    }
+    mapping
+}
+
+fn no_jacks(hit_objects: &mut [HitObject], keycount: usize, seed: u64) {
+    let mut rng = SeededRng::new(seed);
+    let original: Vec<Option<usize>> = hit_objects.iter().map(|h| columns::column_of(h, keycount)).collect();
+    let mut previous_original: Vec<usize> = Vec::new();
+    let mut previous_new: Vec<usize> = Vec::new();
+    // Moment où chaque colonne du résultat peut recevoir une note : après le LN qui l'occupe
+    let mut free_at = vec![f64::MIN; keycount];
+
+    for chord in chords(hit_objects) {
+        let chord: Vec<usize> = chord.into_iter().filter(|&i| original[i].is_some()).collect();
+        let Some(&first) = chord.first() else {
+            continue;
+        };
+        let time = hit_objects[first].start_time;
+        let from_columns: Vec<usize> = chord.iter().map(|&i| original[i].unwrap()).collect();
+
+        let mut best: Option<(usize, Vec<usize>)> = None;
+        // On tire quelques permutations et on garde celle qui crée le moins de jacks,
+        // sans jamais poser une note dans un LN encore tenu
+        for _ in 0..32 {
+            let candidate = {
+                let mut columns: Vec<usize> = (0..keycount).collect();
+                rng.shuffle(&mut columns);
+                columns
+            };
+            if from_columns.iter().any(|&from| free_at[candidate[from]] > time) {
+                continue;
+            }
+            let new_jacks = from_columns
+                .iter()
+                .filter(|&&from| previous_new.contains(&candidate[from]) && !previous_original.contains(&from))
+                .count();
+            if best.as_ref().is_none_or(|(jacks, _)| new_jacks < *jacks) {
+                best = Some((new_jacks, candidate));
+            }
+            if new_jacks == 0 {
+                break;
+            }
+        }
+
+        let mapping = match best {
+            Some((_, mapping)) => mapping,
+            None => fallback_mapping(&from_columns, &free_at, time, &previous_new),
+        };
+        previous_new = from_columns.iter().map(|&c| mapping[c]).collect();
+        previous_original = from_columns;
+        for &i in &chord {
+            let column = mapping[original[i].unwrap()];
+            columns::set_column(&mut hit_objects[i], column, keycount);
+            if let HitObjectKind::Hold(hold) = &hit_objects[i].kind {
+                free_at[column] = time + hold.duration + RELEASE_GAP;
+            }
+        }
+    }
+}
+
+pub fn apply(hit_objects: &mut [HitObject], keycount: usize, transform: &ColumnTransform) {
+    if keycount == 0 {
+        return;
+    }
+    if let ColumnTransform::NoJacks(seed) = transform {
+        no_jacks(hit_objects, keycount, *seed);
+        return;
+    }
+    let Some(mapping) = column_mapping(transform, keycount) else {
+        return;
+    };
+    for hit_object in hit_objects.iter_mut() {
+        if let Some(column) = columns::column_of(hit_object, keycount) {
+            columns::set_column(hit_object, mapping[column], keycount);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosu_map::section::hit_objects::{HitObjectCircle, HitObjectHold};
+    use rosu_map::util::Pos;
+
+    fn note(time: f64, column: usize, keycount: usize) -> HitObject {
+        HitObject {
+            start_time: time,
+            kind: HitObjectKind::Circle(HitObjectCircle {
+                pos: Pos::new(columns::column_x(column, keycount), 192.0),
+                new_combo: false,
+                combo_offset: 0,
+            }),
+            samples: Vec::new(),
+        }
+    }
+
+    fn hold(time: f64, duration: f64, column: usize, keycount: usize) -> HitObject {
+        HitObject {
+            start_time: time,
+            kind: HitObjectKind::Hold(HitObjectHold {
+                pos_x: columns::column_x(column, keycount),
+                duration,
+            }),
+            samples: Vec::new(),
+        }
+    }
+
+    fn columns_after(hit_objects: &[HitObject], keycount: usize, transform: ColumnTransform) -> Vec<usize> {
+        let mut hit_objects = hit_objects.to_vec();
+        apply(&mut hit_objects, keycount, &transform);
+        hit_objects.iter().map(|h| columns::column_of(h, keycount).unwrap()).collect()
+    }
+
+    /// Une note par colonne, de gauche à droite
+    fn stair(keycount: usize) -> Vec<HitObject> {
+        (0..keycount).map(|c| note(c as f64 * 100.0, c, keycount)).collect()
+    }
+
+    /// Aucune note ne commence dans un LN de sa colonne ou moins de RELEASE_GAP après sa fin.
+    fn assert_playable(hit_objects: &[HitObject], keycount: usize) {
+        for (i, a) in hit_objects.iter().enumerate() {
+            let HitObjectKind::Hold(hold) = &a.kind else {
+                continue;
+            };
+            let end = a.start_time + hold.duration + RELEASE_GAP;
+            for (j, b) in hit_objects.iter().enumerate() {
+                let same_column = columns::column_of(a, keycount) == columns::column_of(b, keycount);
+                assert!(
+                    i == j || !same_column || b.start_time < a.start_time || b.start_time >= end,
+                    "note at {} inside the hold at {}",
+                    b.start_time,
+                    a.start_time
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn mirror_reverses_columns() {
+        assert_eq!(columns_after(&stair(4), 4, ColumnTransform::Mirror), [3, 2, 1, 0]);
+        assert_eq!(columns_after(&stair(7), 7, ColumnTransform::Mirror), [6, 5, 4, 3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn rotate_wraps_around() {
+        assert_eq!(columns_after(&stair(4), 4, ColumnTransform::Rotate(1)), [1, 2, 3, 0]);
+        assert_eq!(columns_after(&stair(4), 4, ColumnTransform::Rotate(-1)), [3, 0, 1, 2]);
+        assert_eq!(columns_after(&stair(4), 4, ColumnTransform::Rotate(6)), [2, 3, 0, 1]);
+    }
+
+    #[test]
+    fn hand_swap_keeps_the_middle_column() {
+        assert_eq!(columns_after(&stair(4), 4, ColumnTransform::HandSwap), [2, 3, 0, 1]);
+        assert_eq!(columns_after(&stair(7), 7, ColumnTransform::HandSwap), [4, 5, 6, 3, 0, 1, 2]);
+    }
+
+    #[test]
+    fn transforms_keep_hold_lengths() {
+        let mut hit_objects = vec![hold(0.0, 400.0, 0, 4)];
+        apply(&mut hit_objects, 4, &ColumnTransform::Mirror);
+        assert_eq!(columns::column_of(&hit_objects[0], 4), Some(3));
+        assert!(matches!(&hit_objects[0].kind, HitObjectKind::Hold(h) if h.duration == 400.0));
+    }
+
+    #[test]
+    fn no_jacks_adds_no_jack_to_a_stream() {
+        // 1234 répété : aucune colonne deux fois de suite
+        let stream: Vec<HitObject> = (0..32).map(|i| note(i as f64 * 100.0, i % 4, 4)).collect();
+        for seed in 0..50 {
+            let columns = columns_after(&stream, 4, ColumnTransform::NoJacks(seed));
+            assert!(columns.windows(2).all(|w| w[0] != w[1]), "seed {} made a jack: {:?}", seed, columns);
+        }
+    }
+
+    #[test]
+    fn no_jacks_never_lands_inside_a_hold() {
+        // Un LN long en colonne 1 pendant un trill sur les autres colonnes, puis des accords
+        let mut pattern = vec![hold(0.0, 1000.0, 1, 4)];
+        pattern.extend((1..10).map(|i| note(i as f64 * 100.0, if i % 2 == 0 { 0 } else { 2 }, 4)));
+        pattern.extend((0..8).flat_map(|i| {
+            let time = 1100.0 + i as f64 * 150.0;
+            [hold(time, 100.0, i % 4, 4), note(time, (i + 2) % 4, 4)]
+        }));
+        for seed in 0..100 {
+            let mut hit_objects = pattern.clone();
+            apply(&mut hit_objects, 4, &ColumnTransform::NoJacks(seed));
+            assert_playable(&hit_objects, 4);
+        }
+    }
+
+    #[test]
+    fn no_jacks_falls_back_to_free_columns() {
+        // 3 LN tenus en 4K : la note suivante n'a qu'une colonne possible
+        let pattern = vec![
+            hold(0.0, 1000.0, 0, 4),
+            hold(0.0, 1000.0, 1, 4),
+            hold(0.0, 1000.0, 2, 4),
+            note(500.0, 3, 4),
+        ];
+        for seed in 0..20 {
+            let mut hit_objects = pattern.clone();
+            apply(&mut hit_objects, 4, &ColumnTransform::NoJacks(seed));
+            assert_playable(&hit_objects, 4);
+        }
+    }
+
+    /// Une seed sauvegardée doit toujours redonner le même shuffle.
+    #[test]
+    fn seeded_permutation_is_stable() {
+        assert_eq!(seeded_permutation(42, 7), [2, 4, 6, 0, 3, 1, 5]);
+        assert_eq!(seeded_permutation(0, 4), [2, 1, 0, 3]);
+        assert_eq!(seeded_permutation(12345, 10), [8, 6, 7, 2, 1, 3, 9, 5, 0, 4]);
+    }
+
+    #[test]
+    fn transforms_round_trip_through_text() {
+        let transforms = [
+            ColumnTransform::Mirror,
+            ColumnTransform::Rotate(-3),
+            ColumnTransform::Permutation(vec![1, 0, 3, 2]),
+            ColumnTransform::RandomSeeded(u64::MAX),
+            ColumnTransform::NoJacks(42),
+            ColumnTransform::HandSwap,
+        ];
+        for transform in transforms {
+            assert_eq!(ColumnTransform::parse(&transform.to_string()), Some(transform));
+        }
+        assert_eq!(ColumnTransform::parse("Rotate"), None);
+        assert_eq!(ColumnTransform::parse("Random(abc)"), None);
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use crate::snippets::index;
+use crate::snippets::library::{self, LibraryRoot};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Fichiers qui ont bougé depuis le dernier passage.
+#[derive(Clone, Debug, Default)]
+pub struct LibraryChange {
+    pub added: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+}
+
+impl LibraryChange {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+}
+
+fn snapshot(roots: &[LibraryRoot]) -> HashMap<PathBuf, u64> {
+    library::scan(roots)
+        .into_iter()
+        .map(|f| {
+            let mtime = index::modified_time(&f.path);
+            (f.path, mtime)
+        })
+        .collect()
+}
+
+fn diff(before: &HashMap<PathBuf, u64>, after: &HashMap<PathBuf, u64>) -> LibraryChange {
+    let mut change = LibraryChange::default();
+    for (path, mtime) in after {
+        match before.get(path) {
+            None => change.added.push(path.clone()),
+            Some(old) if old != mtime => change.modified.push(path.clone()),
+            _ => {}
+        }
+    }
+    change.removed = before.keys().filter(|p| !after.contains_key(*p)).cloned().collect();
+    change
+}
+
+/// Surveille les bibliothèques en comparant les dates de modification toutes les 2s.
+/// Pas d'API de notification : ça marche aussi sur les dossiers réseau et synchronisés.
+/// Le thread s'arrête quand le watcher est drop.
+pub struct LibraryWatcher {
+    pub events: Receiver<LibraryChange>,
+    stop: Arc<AtomicBool>,
+}
+
+impl LibraryWatcher {
+    pub fn spawn(roots: Vec<LibraryRoot>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        thread::spawn(move || {
+            let mut known = snapshot(&roots);
+            while !thread_stop.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+                let current = snapshot(&roots);
+                let change = diff(&known, &current);
+                known = current;
+                if !change.is_empty() && tx.send(change).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { events: rx, stop }
+    }
+}
+
+impl Drop for LibraryWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
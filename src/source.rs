@@ -0,0 +1,184 @@
+use eyre::Result;
+use rosu_map::Beatmap;
+use rosu_map::section::general::GameMode;
+use rosu_mem::process::Process;
+use rosu_memory_lib::init_loop;
+use rosu_memory_lib::reader::beatmap::stable::file::get_beatmap_path;
+use rosu_memory_lib::reader::common::stable::memory::get_menu_mods;
+use rosu_memory_lib::reader::gameplay::stable::memory::get_ig_time;
+use rosu_memory_lib::reader::structs::State;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Mutex;
+use std::thread;
+
+/// Ce dont l'app a besoin du jeu : la map ouverte et le temps courant.
+pub trait GameSource {
+    fn name(&self) -> &'static str;
+    fn beatmap_path(&self) -> Result<PathBuf>;
+    /// Temps courant dans l'éditeur ou en jeu, en ms.
+    fn current_time(&self) -> Result<i32>;
+    fn game_mode(&self) -> Result<GameMode> {
+        Ok(Beatmap::from_path(self.beatmap_path()?)?.mode)
+    }
+    fn mods(&self) -> Result<u32>;
+}
+
+/// Lecture directe de la mémoire d'osu! via rosu-memory-lib.
+pub struct MemorySource {
+    process: Process,
+    state: Mutex<State>,
+}
+
+impl MemorySource {
+    pub fn new(process: Process, state: State) -> Self {
+        Self {
+            process,
+            state: Mutex::new(state),
+        }
+    }
+}
+
+impl GameSource for MemorySource {
+    fn name(&self) -> &'static str {
+        "osu! (memory)"
+    }
+
+    fn beatmap_path(&self) -> Result<PathBuf> {
+        let mut state = self.state.lock().map_err(|_| eyre::eyre!("State lock poisoned"))?;
+        Ok(PathBuf::from(get_beatmap_path(&self.process, &mut state)?))
+    }
+
+    fn current_time(&self) -> Result<i32> {
+        let mut state = self.state.lock().map_err(|_| eyre::eyre!("State lock poisoned"))?;
+        Ok(get_ig_time(&self.process, &mut state)?)
+    }
+
+    fn mods(&self) -> Result<u32> {
+        let mut state = self.state.lock().map_err(|_| eyre::eyre!("State lock poisoned"))?;
+        Ok(get_menu_mods(&self.process, &mut state)? as u32)
+    }
+}
+
+/// Source hors-ligne : l'utilisateur choisit un .osu et un temps à la main.
+#[derive(Clone, Debug)]
+pub struct FileSource {
+    pub beatmap_path: String,
+    pub time: i32,
+    pub mods: u32,
+}
+
+impl FileSource {
+    pub fn new() -> Self {
+        Self {
+            beatmap_path: String::new(),
+            time: 0,
+            mods: 0,
+        }
+    }
+}
+
+impl GameSource for FileSource {
+    fn name(&self) -> &'static str {
+        "File"
+    }
+
+    fn beatmap_path(&self) -> Result<PathBuf> {
+        if self.beatmap_path.is_empty() {
+            return Err(eyre::eyre!("No beatmap selected"));
+        }
+        let path = PathBuf::from(&self.beatmap_path);
+        if !path.is_file() {
+            return Err(eyre::eyre!("Beatmap not found: {}", self.beatmap_path));
+        }
+        Ok(path)
+    }
+
+    fn current_time(&self) -> Result<i32> {
+        Ok(self.time)
+    }
+
+    fn mods(&self) -> Result<u32> {
+        Ok(self.mods)
+    }
+}
+
+/// Process et état trouvés par le thread d'attache.
+struct Attached(Process, State);
+
+// Le thread d'attache lâche le process dès qu'il l'a envoyé, seul le thread de l'UI s'en sert ensuite
+unsafe impl Send for Attached {}
+
+/// `init_loop` attend qu'osu! soit lancé sans jamais rendre la main, on l'appelle donc
+/// dans un thread. Le channel se ferme sans message si l'attache échoue pour de bon.
+fn spawn_attach() -> Receiver<Attached> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || match init_loop(500) {
+        Ok((state, process)) => {
+            let _ = tx.send(Attached(process, state));
+        }
+        Err(e) => println!("Could not attach to osu!: {}", e),
+    });
+    rx
+}
+
+/// Les deux backends disponibles, et celui actuellement utilisé.
+/// On démarre sur le fichier, la mémoire prend le relais quand osu! est trouvé.
+pub struct GameSources {
+    pub memory: Option<MemorySource>,
+    pub file: FileSource,
+    pub use_file: bool,
+    attach: Option<Receiver<Attached>>,
+}
+
+impl GameSources {
+    pub fn new() -> Self {
+        Self {
+            memory: None,
+            file: FileSource::new(),
+            use_file: true,
+            attach: Some(spawn_attach()),
+        }
+    }
+
+    pub fn is_attaching(&self) -> bool {
+        self.attach.is_some()
+    }
+
+    /// Relance la recherche d'osu! après un échec.
+    pub fn reconnect(&mut self) {
+        if self.memory.is_none() && self.attach.is_none() {
+            self.attach = Some(spawn_attach());
+        }
+    }
+
+    /// Récupère le process s'il vient d'être trouvé. Bascule sur la mémoire
+    /// sauf si l'utilisateur a déjà choisi une map à la main.
+    pub fn poll_attach(&mut self) -> bool {
+        let Some(attach) = &self.attach else {
+            return false;
+        };
+        match attach.try_recv() {
+            Ok(Attached(process, state)) => {
+                self.memory = Some(MemorySource::new(process, state));
+                self.attach = None;
+                if self.file.beatmap_path.is_empty() {
+                    self.use_file = false;
+                }
+                true
+            }
+            Err(TryRecvError::Disconnected) => {
+                self.attach = None;
+                false
+            }
+            Err(TryRecvError::Empty) => false,
+        }
+    }
+
+    pub fn active(&self) -> &dyn GameSource {
+        match &self.memory {
+            Some(memory) if !self.use_file => memory,
+            _ => &self.file,
+        }
+    }
+}
@@ -1,8 +1,23 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
 use std::time::{Instant, Duration};
-use rosu_mem::process::Process;
-use rosu_memory_lib::reader::structs::State;
-use crate::snippets::structs::{SnippetsMaker, Snippets};
+use rosu_map::Beatmap;
+use rosu_map::section::general::GameMode;
+use crate::snippets::structs::{SnippetsMaker, Snippets, NextUpdate, InsertOptions, PreparedInsert};
+use crate::snippets::history::InsertHistory;
+use crate::snippets::format;
+use crate::snippets::timing;
+use crate::snippets::pack::{self, Pack};
+use crate::snippets::library::{self, LibraryKind, LibraryRoot};
+use crate::snippets::index::{self, IndexEntry, LibraryIndex};
+use crate::snippets::watcher::LibraryWatcher;
+use crate::snippets::rhythm::RhythmTransform;
+use crate::snippets::tags;
+use crate::snippets::pattern::{self, AutoTagReport, PatternCache};
+use crate::source::GameSources;
+use crate::hotkeys::{self, Hotkey, HotkeyAction, HotkeyBindings};
+use crate::settings::Settings;
 use crate::ui::mania::ManiaRenderer;
 
 pub struct Notification {
@@ -25,15 +40,33 @@ impl Notification {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct UnloadedSnippet {
-    pub name: String,
-    pub tags: Vec<String>,
+/// Valeurs en cours d'édition pour ajouter une transformation.
+pub struct TransformInputs {
+    pub rotate: i32,
+    pub permutation: String,
+    pub seed: u64,
+    pub stretch_ratio: f64,
+    pub ln_length: f64,
+}
+
+impl TransformInputs {
+    pub fn new() -> Self {
+        Self {
+            rotate: 1,
+            permutation: String::new(),
+            seed: rand::random(),
+            stretch_ratio: 4.0 / 3.0,
+            ln_length: 200.0,
+        }
+    }
 }
 
 pub struct AppState {
     pub snippets: Vec<Snippets>,
-    pub unloaded_snippets: Vec<UnloadedSnippet>,
+    /// Fichiers des bibliothèques, depuis l'index (pas encore décodés)
+    pub unloaded_snippets: Vec<IndexEntry>,
+    index_updates: Option<Receiver<LibraryIndex>>,
+    library_watcher: LibraryWatcher,
     pub snippets_maker: SnippetsMaker,
     pub selected_snippet: Option<usize>,
     pub mania_renderer: Option<ManiaRenderer>,
@@ -42,16 +75,52 @@ pub struct AppState {
     pub snippet_speed: f32,
     pub show_save_dialog: bool,
     pub save_filename: String,
-    pub process: Arc<Process>,
-    pub state: Arc<Mutex<State>>,
+    pub sources: GameSources,
     pub notification: Option<Notification>,
+    pub history: InsertHistory,
+    pub insert_options: InsertOptions,
+    pub pending_insert: Option<PreparedInsert>,
+    pub transform_inputs: TransformInputs,
+    pub settings: Settings,
+    pub hotkeys: Arc<Mutex<HotkeyBindings>>,
+    pub hotkey_events: Receiver<HotkeyAction>,
+    pub hotkey_inputs: Vec<String>,
+    pub show_paste_dialog: bool,
+    pub paste_text: String,
+    pub paste_keycount: usize,
+    /// Bpm de la map d'où viennent les lignes collées
+    pub paste_bpm: f64,
+    pub pack_path: String,
+    /// Tag à exporter, vide pour exporter la snippet sélectionnée
+    pub pack_tag: String,
+    /// Racine choisie dans le dialogue de sauvegarde
+    pub save_root: usize,
+    pub library_input: String,
+    pub library_kind_input: LibraryKind,
+    pub search_text: String,
+    pub tag_rename_from: String,
+    pub tag_rename_to: String,
+    /// Confiance minimale pour l'auto-tag de la bibliothèque
+    pub auto_tag_threshold: f64,
+    /// Rapport de l'auto-tag en cours, `None` quand il n'y en a pas
+    auto_tag_updates: Option<Receiver<AutoTagReport>>,
+    /// Suggestions de tags de la snippet affichée
+    pub pattern_cache: PatternCache,
 }
 
 impl AppState {
-    pub fn new(process: Arc<Process>, state: Arc<Mutex<State>>) -> Self {
+    pub fn new(sources: GameSources, ctx: egui::Context) -> Self {
+        let settings = Settings::load();
+        let hotkeys = Arc::new(Mutex::new(settings.hotkeys.clone()));
+        let hotkey_events = hotkeys::spawn_listener(hotkeys.clone(), ctx);
+        let hotkey_inputs = HotkeyAction::ALL.iter()
+            .map(|a| settings.hotkeys.get(*a).map(|h| h.to_string()).unwrap_or_default())
+            .collect();
         let mut app_state = Self {
             snippets: Vec::new(),
-            unloaded_snippets: Vec::new(),
+            unloaded_snippets: LibraryIndex::load().entries,
+            index_updates: None,
+            library_watcher: LibraryWatcher::spawn(settings.libraries.clone()),
             snippets_maker: SnippetsMaker::new(),
             selected_snippet: None,
             mania_renderer: None,
@@ -60,32 +129,521 @@ impl AppState {
             snippet_speed: 1.0,
             show_save_dialog: false,
             save_filename: String::new(),
-            process,
-            state,
+            sources,
             notification: None,
+            history: InsertHistory::load(),
+            insert_options: InsertOptions::new(),
+            pending_insert: None,
+            transform_inputs: TransformInputs::new(),
+            settings,
+            hotkeys,
+            hotkey_events,
+            hotkey_inputs,
+            show_paste_dialog: false,
+            paste_text: String::new(),
+            paste_keycount: 4,
+            paste_bpm: 120.0,
+            pack_path: format!("snippets.{}", pack::PACK_EXTENSION),
+            pack_tag: String::new(),
+            save_root: 0,
+            library_input: String::new(),
+            library_kind_input: LibraryKind::Personal,
+            search_text: String::new(),
+            tag_rename_from: String::new(),
+            tag_rename_to: String::new(),
+            auto_tag_threshold: pattern::SUGGEST_THRESHOLD,
+            auto_tag_updates: None,
+            pattern_cache: PatternCache::new(),
         };
-        app_state.load_available_snippets();
+        app_state.reload_available_snippets();
         app_state
     }
 
-    pub fn load_available_snippets(&mut self) {
-        if let Ok(entries) = std::fs::read_dir("snippets") {
-            for entry in entries.flatten() {
-                if let Some(filename) = entry.file_name().to_str() {
-                    if filename.ends_with(".snippets") {
-                        let mut snippet = Snippets::new();
-                        if let Ok(_) = snippet.load_snippets(filename) {
-                            self.unloaded_snippets.push(UnloadedSnippet {
-                                name: filename.to_string(),
-                                tags: snippet.tags.clone(),
-                            });
-                        }
-                    }
+    /// Relance le rafraîchissement de l'index en arrière-plan, l'ancien reste affiché en attendant.
+    pub fn reload_available_snippets(&mut self) {
+        let current = LibraryIndex {
+            entries: self.unloaded_snippets.clone(),
+        };
+        self.index_updates = Some(index::spawn_refresh(current, self.settings.libraries.clone()));
+    }
+
+    pub fn handle_index_updates(&mut self) {
+        let mut changed = false;
+        while let Ok(change) = self.library_watcher.events.try_recv() {
+            changed = true;
+            // Nos propres sauvegardes mettent à jour disk_mtime, elles ne rendent pas la snippet stale
+            for snippet in self.snippets.iter_mut() {
+                let Some(path) = &snippet.file_path else {
+                    continue;
+                };
+                if change.modified.contains(path) || change.removed.contains(path) {
+                    snippet.stale = index::modified_time(path) != snippet.disk_mtime;
+                }
+            }
+        }
+        if changed {
+            self.reload_available_snippets();
+        }
+
+        let Some(updates) = &self.index_updates else {
+            return;
+        };
+        if let Ok(index) = updates.try_recv() {
+            self.unloaded_snippets = index.entries;
+            self.index_updates = None;
+        }
+    }
+
+    /// Recharge la snippet sélectionnée depuis son fichier modifié sur le disque.
+    pub fn reload_selected(&mut self) {
+        let Some(snippet) = self.selected_snippet.and_then(|idx| self.snippets.get_mut(idx)) else {
+            return;
+        };
+        let Some(path) = snippet.file_path.clone() else {
+            return;
+        };
+        let mut reloaded = Snippets::new();
+        let message = match reloaded.load_snippets(&path) {
+            Ok(_) => {
+                reloaded.name = snippet.name.clone();
+                *snippet = reloaded;
+                format!("Reloaded {}", snippet.name)
+            }
+            Err(e) => format!("Error: {}", e),
+        };
+        self.start_time = Instant::now();
+        self.show_notification(message);
+    }
+
+    /// Garde la version en mémoire, la prochaine sauvegarde écrasera le fichier.
+    pub fn keep_selected(&mut self) {
+        if let Some(snippet) = self.selected_snippet.and_then(|idx| self.snippets.get_mut(idx)) {
+            snippet.stale = false;
+            if let Some(path) = &snippet.file_path {
+                snippet.disk_mtime = index::modified_time(path);
+            }
+        }
+    }
+
+    /// Racine où sauvegarder : celle choisie si elle est modifiable, sinon la première qui l'est.
+    pub fn writable_root(&self) -> Option<&LibraryRoot> {
+        let roots = &self.settings.libraries;
+        roots
+            .get(self.save_root)
+            .filter(|r| r.kind.is_writable())
+            .or_else(|| roots.iter().find(|r| r.kind.is_writable()))
+    }
+
+    pub fn save_selected(&mut self, filename: &str) {
+        let Some(root) = self.writable_root().map(|r| r.path.clone()) else {
+            self.show_notification("No writable library configured".to_string());
+            return;
+        };
+        let Some(snippet) = self.selected_snippet.and_then(|idx| self.snippets.get_mut(idx)) else {
+            return;
+        };
+        let message = match snippet.save_snippets_to_file(&root, filename) {
+            Ok(_) => format!("Successfully saved {}", filename),
+            Err(e) => format!("Error: {}", e),
+        };
+        self.reload_available_snippets();
+        self.show_notification(message);
+    }
+
+    /// Réécrit le fichier de la snippet sélectionnée, sauf s'il est dans une bibliothèque en lecture seule
+    /// ou s'il a changé sur le disque depuis le chargement.
+    pub fn update_selected(&mut self) {
+        let Some(snippet) = self.selected_snippet.and_then(|idx| self.snippets.get_mut(idx)) else {
+            return;
+        };
+        let Some(path) = snippet.file_path.clone() else {
+            return;
+        };
+        let read_only = library::root_of(&self.settings.libraries, &path).is_some_and(|r| !r.kind.is_writable());
+        let message = if read_only {
+            format!("{} is in a read-only library", snippet.name)
+        } else if snippet.stale {
+            // Écraser sans choix explicite perdrait la version du disque
+            format!("{} changed on disk, reload it or keep yours first", snippet.name)
+        } else {
+            match snippet.save_to_path(&path) {
+                Ok(_) => format!("Updated {}", snippet.name),
+                Err(e) => format!("Error: {}", e),
+            }
+        };
+        self.show_notification(message);
+    }
+
+    pub fn add_library(&mut self) {
+        let path = self.library_input.trim();
+        if path.is_empty() {
+            return;
+        }
+        self.settings.libraries.push(LibraryRoot::new(PathBuf::from(path), self.library_kind_input));
+        self.library_input.clear();
+        self.apply_libraries();
+    }
+
+    pub fn remove_library(&mut self, index: usize) {
+        if index < self.settings.libraries.len() {
+            self.settings.libraries.remove(index);
+            self.save_root = 0;
+            self.apply_libraries();
+        }
+    }
+
+    /// Sauvegarde la liste des bibliothèques et rescanne.
+    pub fn apply_libraries(&mut self) {
+        self.library_watcher = LibraryWatcher::spawn(self.settings.libraries.clone());
+        let message = match self.settings.save() {
+            Ok(_) => "Libraries saved".to_string(),
+            Err(e) => format!("Error: {}", e),
+        };
+        self.reload_available_snippets();
+        self.show_notification(message);
+    }
+
+    /// Exporte les fichiers portant `pack_tag`, ou la snippet sélectionnée si aucun tag n'est choisi.
+    pub fn export_pack(&mut self) {
+        let mut pack = Pack::new(if self.pack_tag.is_empty() { "selection" } else { &self.pack_tag });
+        if self.pack_tag.is_empty() {
+            let Some(snippet) = self.selected_snippet.and_then(|idx| self.snippets.get(idx)) else {
+                self.show_notification("No snippet selected".to_string());
+                return;
+            };
+            let file_name = if snippet.name.ends_with(".snippets") {
+                snippet.name.clone()
+            } else {
+                format!("{}.snippets", snippet.name)
+            };
+            match format::write(snippet) {
+                Ok(content) => pack.add(&file_name, content),
+                Err(e) => {
+                    self.show_notification(format!("Error: {}", e));
+                    return;
+                }
+            }
+        } else {
+            for unloaded in self.unloaded_snippets.iter().filter(|u| u.tags.iter().any(|t| tags::tag_matches(t, &self.pack_tag))) {
+                if let Ok(content) = std::fs::read_to_string(&unloaded.path) {
+                    pack.add(&unloaded.name, content);
+                }
+            }
+        }
+
+        if pack.entries.is_empty() {
+            self.show_notification(format!("No saved snippet tagged {}", self.pack_tag));
+            return;
+        }
+        let message = match pack.save(Path::new(&self.pack_path)) {
+            Ok(_) => format!("Exported {} snippets to {}", pack.entries.len(), self.pack_path),
+            Err(e) => format!("Error: {}", e),
+        };
+        self.show_notification(message);
+    }
+
+    pub fn import_pack(&mut self) {
+        let Some(root) = self.writable_root().map(|r| r.path.clone()) else {
+            self.show_notification("No writable library configured".to_string());
+            return;
+        };
+        let result = Pack::load(Path::new(&self.pack_path)).and_then(|pack| pack.import_into(&root, &self.unloaded_snippets));
+        let message = match result {
+            Ok(report) => report.summary(),
+            Err(e) => format!("Error: {}", e),
+        };
+        self.reload_available_snippets();
+        self.show_notification(message);
+    }
+
+    /// Renomme `tag_rename_from` en `tag_rename_to` dans toute la bibliothèque et les snippets chargées.
+    /// Un nom déjà utilisé fusionne les deux tags.
+    pub fn rename_tag(&mut self) {
+        let from = self.tag_rename_from.trim().to_string();
+        let to = self.tag_rename_to.trim().to_string();
+        if from.is_empty() || to.is_empty() || to.contains(char::is_whitespace) {
+            self.show_notification("Error: tag names can't be empty or contain spaces".to_string());
+            return;
+        }
+        let report = tags::rename_in_library(&self.unloaded_snippets, &self.settings.libraries, &from, &to);
+        for snippet in self.snippets.iter_mut() {
+            if snippet.file_path.as_ref().is_some_and(|p| report.read_only.contains(p)) {
+                continue;
+            }
+            tags::rename_tag(&mut snippet.tags, &from, &to);
+            // Le fichier a été réécrit par nous, ce n'est pas une modification externe
+            if let Some(path) = snippet.file_path.as_ref().filter(|p| report.rewritten.contains(p)) {
+                snippet.disk_mtime = index::modified_time(path);
+            }
+        }
+        for (path, e) in &report.failed {
+            println!("Failed to rename tag in {}: {}", path.display(), e);
+        }
+        self.tag_rename_from = to.clone();
+        self.tag_rename_to.clear();
+        self.reload_available_snippets();
+        self.show_notification(report.summary(&from, &to));
+    }
+
+    /// Ajoute les tags des patterns détectés à tous les fichiers modifiables de la bibliothèque.
+    /// Les fichiers sont lus et réécrits dans un thread, voir `handle_auto_tag`.
+    pub fn auto_tag_library(&mut self) {
+        if self.is_auto_tagging() {
+            return;
+        }
+        self.auto_tag_updates = Some(pattern::spawn_tag_library(
+            self.unloaded_snippets.clone(),
+            self.settings.libraries.clone(),
+            self.auto_tag_threshold,
+        ));
+        self.show_notification("Auto-tagging library...".to_string());
+    }
+
+    pub fn is_auto_tagging(&self) -> bool {
+        self.auto_tag_updates.is_some()
+    }
+
+    pub fn handle_auto_tag(&mut self) {
+        let Some(updates) = &self.auto_tag_updates else {
+            return;
+        };
+        let Ok(report) = updates.try_recv() else {
+            return;
+        };
+        self.auto_tag_updates = None;
+        for snippet in self.snippets.iter_mut() {
+            let Some(tagged) = report.tagged.iter().find(|t| snippet.file_path.as_ref() == Some(&t.path)) else {
+                continue;
+            };
+            for tag in &tagged.added {
+                if !snippet.tags.contains(tag) {
+                    snippet.tags.push(tag.clone());
+                }
+            }
+            // Le watcher a pu voir notre écriture avant ce rapport, ce n'est pas une modification externe
+            if index::modified_time(&tagged.path) == tagged.mtime {
+                snippet.stale = false;
+            }
+            snippet.disk_mtime = tagged.mtime;
+        }
+        for (path, e) in &report.failed {
+            println!("Failed to auto-tag {}: {}", path.display(), e);
+        }
+        self.reload_available_snippets();
+        self.show_notification(report.summary());
+    }
+
+    pub fn mark_next(&mut self) {
+        let result = self.snippets_maker.set_next(self.sources.active());
+        let message = match result {
+            Ok(NextUpdate::TimeStart) => format!("End at {}", self.snippets_maker.time_end),
+            Ok(NextUpdate::TimeEnd) => format!("Start at {}", self.snippets_maker.time_start),
+            Err(e) => format!("Error: {}", e),
+        };
+        self.show_notification(message);
+    }
+
+    pub fn mark_start(&mut self) {
+        let message = match self.sources.active().current_time() {
+            Ok(time) => {
+                self.snippets_maker.set_start(time);
+                format!("Start at {}", time)
+            }
+            Err(e) => format!("Error: {}", e),
+        };
+        self.show_notification(message);
+    }
+
+    pub fn mark_end(&mut self) {
+        let message = match self.sources.active().current_time() {
+            Ok(time) => {
+                self.snippets_maker.set_end(time);
+                format!("End at {}", time)
+            }
+            Err(e) => format!("Error: {}", e),
+        };
+        self.show_notification(message);
+    }
+
+    pub fn create_snippet(&mut self) {
+        let snippets_maker = self.snippets_maker.clone();
+        let mut new_snippets = Snippets::new();
+        let creation_result = new_snippets.load_snippets_from_source(
+            self.sources.active(),
+            &snippets_maker
+        );
+
+        match creation_result {
+            Ok(_) => {
+                new_snippets.name = format!("New Snippet {}", self.snippets.len() + 1);
+                let suggested: Vec<&str> = pattern::suggestions(&new_snippets, pattern::SUGGEST_THRESHOLD)
+                    .iter()
+                    .map(|s| s.pattern.tag())
+                    .collect();
+                self.snippets.push(new_snippets);
+                self.selected_snippet = Some(self.snippets.len() - 1);
+                self.start_time = Instant::now();
+                if suggested.is_empty() {
+                    self.show_notification("New snippet created".to_string());
+                } else {
+                    self.show_notification(format!("New snippet created, suggested tags: {}", suggested.join(", ")));
                 }
             }
+            Err(_) => {
+                self.show_notification("Failed to create snippet".to_string());
+            }
+        }
+    }
+
+    pub fn insert_selected(&mut self) {
+        let Some(snippet) = self.selected_snippet.and_then(|idx| self.snippets.get(idx)) else {
+            self.show_notification("No snippet selected".to_string());
+            return;
+        };
+        let result = snippet.insert_snippets_to_beatmap(self.sources.active(), &mut self.history, &self.insert_options);
+        let message = match result {
+            Ok(report) => report.summary(),
+            Err(e) => format!("Error: {}", e),
+        };
+        self.show_notification(message);
+    }
+
+    /// Prépare l'insertion et la garde en attente pour afficher le résumé des conflits.
+    pub fn prepare_selected_insert(&mut self) {
+        let Some(snippet) = self.selected_snippet.and_then(|idx| self.snippets.get(idx)) else {
+            self.show_notification("No snippet selected".to_string());
+            return;
+        };
+        match snippet.prepare_insert(self.sources.active(), &self.insert_options) {
+            Ok(prepared) => self.pending_insert = Some(prepared),
+            Err(e) => self.show_notification(format!("Error: {}", e)),
         }
     }
 
+    pub fn confirm_pending_insert(&mut self) {
+        let Some(prepared) = self.pending_insert.take() else {
+            return;
+        };
+        let message = match prepared.commit(&mut self.history, self.insert_options.conflict_mode) {
+            Ok(report) => report.summary(),
+            Err(e) => format!("Error: {}", e),
+        };
+        self.show_notification(message);
+    }
+
+    pub fn apply_rhythm_transform(&mut self, transform: RhythmTransform) {
+        let Some(snippet) = self.selected_snippet.and_then(|idx| self.snippets.get(idx)) else {
+            return;
+        };
+        let new_snippet = transform.apply(snippet);
+        let name = new_snippet.name.clone();
+        self.snippets.push(new_snippet);
+        self.selected_snippet = Some(self.snippets.len() - 1);
+        self.start_time = Instant::now();
+        self.show_notification(format!("Created {}", name));
+    }
+
+    /// Ouvre le dialogue de collage, avec le keycount et le bpm de la map ouverte si on peut les lire.
+    pub fn open_paste_dialog(&mut self) {
+        self.show_paste_dialog = true;
+        let source = self.sources.active();
+        let Ok(beatmap) = source.beatmap_path().and_then(|path| Ok(Beatmap::from_path(path)?)) else {
+            return;
+        };
+        let time = source.current_time().unwrap_or_default() as f64;
+        if let Some(point) = timing::point_at(&beatmap.control_points.timing_points, time).filter(|t| t.beat_len > 0.0) {
+            self.paste_bpm = (60000.0 / point.beat_len * 100.0).round() / 100.0;
+        }
+        if beatmap.mode == GameMode::Mania {
+            self.paste_keycount = (beatmap.circle_size as usize).clamp(1, 18);
+        }
+    }
+
+    pub fn paste_snippet(&mut self) {
+        match Snippets::from_hit_object_lines(&self.paste_text, self.paste_keycount, self.paste_bpm) {
+            Ok(mut snippet) => {
+                snippet.name = format!("Pasted Snippet {}", self.snippets.len() + 1);
+                let count = snippet.hit_objects.len();
+                self.snippets.push(snippet);
+                self.selected_snippet = Some(self.snippets.len() - 1);
+                self.start_time = Instant::now();
+                self.show_paste_dialog = false;
+                self.paste_text.clear();
+                self.show_notification(format!("Pasted {} objects", count));
+            }
+            Err(e) => self.show_notification(format!("Error: {}", e)),
+        }
+    }
+
+    /// Indique la map et le temps d'origine de la snippet et copie le timestamp pour l'éditeur.
+    /// Avec la source fichier on se place directement au début de la capture.
+    pub fn jump_to_source(&mut self, ctx: &egui::Context) {
+        let Some(snippet) = self.selected_snippet.and_then(|idx| self.snippets.get(idx)) else {
+            return;
+        };
+        let source = snippet.source.clone();
+        if !source.is_known() {
+            self.show_notification("No source recorded for this snippet".to_string());
+            return;
+        }
+        ctx.copy_text(format!("{} - ", source.timestamp()));
+        if self.sources.use_file {
+            self.sources.file.time = source.start;
+        }
+        self.show_notification(format!("{} at {} (timestamp copied)", source.label(), source.timestamp()));
+    }
+
+    pub fn cycle_snippet(&mut self) {
+        if self.snippets.is_empty() {
+            self.show_notification("No snippet loaded".to_string());
+            return;
+        }
+        let next = self.selected_snippet.map(|idx| (idx + 1) % self.snippets.len()).unwrap_or(0);
+        self.selected_snippet = Some(next);
+        self.start_time = Instant::now();
+        self.show_notification(format!("Selected {}", self.snippets[next].name));
+    }
+
+    pub fn handle_game_source(&mut self) {
+        if self.sources.poll_attach() {
+            self.show_notification("Attached to osu!".to_string());
+        }
+    }
+
+    /// Exécute les actions reçues du thread de hotkeys.
+    pub fn handle_hotkeys(&mut self) {
+        while let Ok(action) = self.hotkey_events.try_recv() {
+            match action {
+                HotkeyAction::MarkStart => self.mark_start(),
+                HotkeyAction::MarkEnd => self.mark_end(),
+                HotkeyAction::CreateSnippet => self.create_snippet(),
+                HotkeyAction::InsertSelected => self.insert_selected(),
+                HotkeyAction::CycleSnippet => self.cycle_snippet(),
+            }
+        }
+    }
+
+    pub fn apply_hotkeys(&mut self) {
+        let mut invalid = Vec::new();
+        for (action, input) in HotkeyAction::ALL.iter().zip(self.hotkey_inputs.iter()) {
+            match Hotkey::parse(input) {
+                Some(hotkey) => self.settings.hotkeys.set(*action, hotkey),
+                None => invalid.push(action.label()),
+            }
+        }
+        if let Ok(mut bindings) = self.hotkeys.lock() {
+            *bindings = self.settings.hotkeys.clone();
+        }
+        let message = if !invalid.is_empty() {
+            format!("Invalid hotkey for: {}", invalid.join(", "))
+        } else if let Err(e) = self.settings.save() {
+            format!("Error: {}", e)
+        } else {
+            "Hotkeys saved".to_string()
+        };
+        self.show_notification(message);
+    }
+
     pub fn show_notification(&mut self, message: String) {
         self.notification = Some(Notification::new(message));
     }
@@ -11,7 +11,7 @@ pub fn render_central_panel(app_state: &mut AppState, ctx: &egui::Context) {
 
                 if let Some(renderer) = &mut app_state.mania_renderer {
                     let current_time = (start_time.elapsed().as_secs_f64() * speed as f64) * 1000.0;
-                    renderer.render(ui, &snippet.hit_objects, current_time, scroll_time, speed as f64, snippet.keycount);
+                    renderer.render(ui, &snippet.transformed_hit_objects(), current_time, scroll_time, speed as f64, snippet.keycount);
                 }
             }
         } else {
@@ -0,0 +1,93 @@
+use egui;
+use crate::ui::app_state::AppState;
+use crate::snippets::conflict::{ConflictKind, ConflictMode};
+
+pub fn render_insert_dialog(app_state: &mut AppState, ctx: &egui::Context) {
+    let Some(prepared) = &app_state.pending_insert else {
+        return;
+    };
+    let mut confirm = false;
+    let mut cancel = false;
+
+    egui::Window::new("Insert Snippet")
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label(format!("{} at {:.0}ms", prepared.snippet_name, prepared.placement_time));
+            ui.label(format!("Objects to insert: {}", prepared.inserted.len()));
+            if let Some(seed) = prepared.shuffle_seed {
+                ui.label(format!("Shuffle seed: {}", seed));
+            }
+            if prepared.source_keycount != prepared.keycount {
+                ui.label(format!(
+                    "{}K -> {}K ({})",
+                    prepared.source_keycount,
+                    prepared.keycount,
+                    app_state.insert_options.keycount_strategy.label()
+                ));
+            }
+            ui.label(format!("Hitsounds: {}", app_state.insert_options.hitsound_mode.label()));
+            if prepared.snap.moved > 0 {
+                ui.label(format!(
+                    "Snapped: {} objects (max {:.1}ms)",
+                    prepared.snap.moved, prepared.snap.max_shift
+                ));
+            }
+            if prepared.snap.lengthened > 0 {
+                ui.label(format!("Lengthened: {} holds shorter than a tick", prepared.snap.lengthened));
+            }
+            if prepared.overlaps > 0 {
+                ui.label(format!("Overlapping snippet notes removed or shortened: {}", prepared.overlaps));
+            }
+
+            ui.group(|ui| {
+                ui.heading("Conflicts");
+                if prepared.conflicts.is_empty() {
+                    ui.label("No conflict");
+                } else {
+                    ui.label(format!("Overlapping notes: {}", prepared.conflicts.count(ConflictKind::Overlap)));
+                    ui.label(format!("Notes inside LNs: {}", prepared.conflicts.count(ConflictKind::InsideHold)));
+                    ui.label(format!("Notes too close: {}", prepared.conflicts.count(ConflictKind::TooClose)));
+                    egui::ScrollArea::vertical().max_height(120.0).show(ui, |ui| {
+                        for conflict in &prepared.conflicts.conflicts {
+                            let (Some(new), Some(old)) = (
+                                prepared.inserted.get(conflict.inserted),
+                                prepared.beatmap.hit_objects.get(conflict.existing),
+                            ) else {
+                                continue;
+                            };
+                            ui.label(format!(
+                                "{:.0}ms: {} with the note at {:.0}ms",
+                                new.start_time,
+                                conflict.kind.label(),
+                                old.start_time
+                            ));
+                        }
+                    });
+                }
+            });
+
+            egui::ComboBox::from_label("Resolution")
+                .selected_text(app_state.insert_options.conflict_mode.label())
+                .show_ui(ui, |ui| {
+                    for mode in ConflictMode::ALL {
+                        ui.selectable_value(&mut app_state.insert_options.conflict_mode, mode, mode.label());
+                    }
+                });
+
+            ui.horizontal(|ui| {
+                if ui.button("Insert").clicked() {
+                    confirm = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    cancel = true;
+                }
+            });
+        });
+
+    if confirm {
+        app_state.confirm_pending_insert();
+    } else if cancel {
+        app_state.pending_insert = None;
+    }
+}
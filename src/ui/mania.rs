@@ -1,5 +1,8 @@
 use egui::{self, Rect, Vec2, pos2};
 use rosu_map::section::hit_objects::{HitObject, HitObjectKind};
+use rosu_map::section::hit_objects::hit_samples::{HitSampleInfo, HitSampleInfoName};
+use crate::snippets::columns;
+use crate::utils;
 
 pub struct ManiaRenderer {
     column_width: f32,
@@ -16,13 +19,22 @@ impl ManiaRenderer {
         }
     }
 
-    fn render_hold(&self, ui: &mut egui::Ui, x_pos: f32, start_y: f32, end_y: f32,  judgment_line_y: f32) {
+    fn render_hold(&self, ui: &mut egui::Ui, x_pos: f32, start_y: f32, end_y: f32,  judgment_line_y: f32, samples: &[HitSampleInfo]) {
         let note_width = self.note_size * 0.8;
         let x_center = x_pos + (self.column_width - note_width) / 2.0;
 
         // Couleurs
-        let body_color = egui::Color32::from_rgb(200, 200, 200); // Blanc gris pour le body
-        let cap_color = egui::Color32::from_rgb(0, 174, 255);    // Bleu pour le cap
+        let has_file = samples.iter().any(|s| matches!(s.name, HitSampleInfoName::File(_)));
+        let body_color = if has_file {
+            egui::Color32::from_rgb(200, 170, 230) // Violet si le LN a un sample custom
+        } else {
+            egui::Color32::from_rgb(200, 200, 200) // Blanc gris pour le body
+        };
+        let cap_color = if utils::hit_sound_type(samples) != 0 {
+            egui::Color32::from_rgb(255, 200, 0) // Jaune si le LN a des additions
+        } else {
+            egui::Color32::from_rgb(0, 174, 255) // Bleu pour le cap
+        };
         
         // Hold body (rectangle)
         // On utilise directement start_y et end_y, mais on limite à la judgment line
@@ -111,7 +123,7 @@ impl ManiaRenderer {
                     // Draw hold notes first so they appear behind regular notes
                     for hit_object in hit_objects.iter().filter(|h| matches!(h.kind, HitObjectKind::Hold(_))) {
                         if let HitObjectKind::Hold(h) = &hit_object.kind {
-                            let column = (h.pos_x / 512.0 * keycount as f32) as usize % keycount;
+                            let column = columns::column_from_x(h.pos_x, keycount);
                             let x_pos = available_rect.min.x + (column as f32 * self.column_width);
                             
                             let note_time = hit_object.start_time + scroll_time_ms as f64;
@@ -128,7 +140,7 @@ impl ManiaRenderer {
                             
                             // Draw hold if the end hasn't passed the judgment line yet
                             if end_y_pos <= judgment_line_y {
-                                self.render_hold(ui, x_pos, y_pos, end_y_pos, judgment_line_y);
+                                self.render_hold(ui, x_pos, y_pos, end_y_pos, judgment_line_y, &hit_object.samples);
                             }
                         }
                     }
@@ -144,11 +156,11 @@ impl ManiaRenderer {
                         if y_pos <= judgment_line_y {
                             let x_pos = match &hit_object.kind {
                                 HitObjectKind::Circle(h) => {
-                                    let column = (h.pos.x / 512.0 * keycount as f32) as usize % keycount;
+                                    let column = columns::column_from_x(h.pos.x, keycount);
                                     available_rect.min.x + (column as f32 * self.column_width)
                                 }
                                 HitObjectKind::Hold(h) => {
-                                    let column = (h.pos_x / 512.0 * keycount as f32) as usize % keycount;
+                                    let column = columns::column_from_x(h.pos_x, keycount);
                                     available_rect.min.x + (column as f32 * self.column_width)
                                 }
                                 _ => continue,
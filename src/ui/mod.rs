@@ -5,10 +5,14 @@ pub mod central_panel;
 pub mod right_panel;
 pub mod save_dialog;
 pub mod toast;
+pub mod insert_dialog;
+pub mod paste_dialog;
 
 pub use app_state::AppState;
 pub use side_panel::render_side_panel;
 pub use central_panel::render_central_panel;
 pub use right_panel::render_right_panel;
 pub use save_dialog::render_save_dialog;
-pub use toast::render_toast; 
+pub use toast::render_toast;
+pub use insert_dialog::render_insert_dialog;
+pub use paste_dialog::render_paste_dialog;
@@ -0,0 +1,41 @@
+use egui;
+use crate::ui::app_state::AppState;
+
+pub fn render_paste_dialog(app_state: &mut AppState, ctx: &egui::Context) {
+    if !app_state.show_paste_dialog {
+        return;
+    }
+    let mut paste = false;
+    let mut cancel = false;
+
+    egui::Window::new("Paste Snippet")
+        .collapsible(false)
+        .resizable(true)
+        .show(ctx, |ui| {
+            ui.label("Paste hit object lines copied from the osu! editor:");
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut app_state.paste_text)
+                        .code_editor()
+                        .desired_rows(8),
+                );
+            });
+            ui.add(egui::DragValue::new(&mut app_state.paste_keycount).range(1..=18).suffix("K").prefix("Key count: "));
+            ui.add(egui::DragValue::new(&mut app_state.paste_bpm).range(1.0..=1000.0).speed(0.5).prefix("Bpm of the source map: "));
+            ui.horizontal(|ui| {
+                if ui.button("Create").clicked() {
+                    paste = true;
+                }
+                if ui.button("Cancel").clicked() {
+                    cancel = true;
+                }
+            });
+        });
+
+    if paste {
+        app_state.paste_snippet();
+    } else if cancel {
+        app_state.show_paste_dialog = false;
+        app_state.paste_text.clear();
+    }
+}
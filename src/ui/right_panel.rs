@@ -1,11 +1,40 @@
 use egui;
 use crate::ui::app_state::AppState;
-use crate::ui::save_dialog;
-use crate::snippets::structs::{Snippets, NextUpdate};
+use crate::ui::{insert_dialog, paste_dialog, save_dialog};
+use crate::snippets::structs::Snippets;
+use crate::snippets::timing;
+use crate::snippets::conflict::ConflictMode;
+use crate::snippets::keycount::KeycountStrategy;
+use crate::snippets::hitsound::HitsoundMode;
+use crate::snippets::transform::ColumnTransform;
+use crate::snippets::rhythm::RhythmTransform;
+use crate::hotkeys::HotkeyAction;
+use crate::snippets::library::LibraryKind;
+use crate::snippets::pattern::{self, PatternCache};
+use crate::utils;
 
-fn render_snippet_settings(snippet: &mut Snippets, ui: &mut egui::Ui) {
-    ui.checkbox(&mut snippet.should_shuffle, "Shuffle columns on insert");
+fn render_snippet_settings(snippet: &mut Snippets, patterns: &mut PatternCache, ui: &mut egui::Ui) {
+    let mut shuffle = snippet.shuffle_seed.is_some();
+    if ui.checkbox(&mut shuffle, "Shuffle columns on insert").changed() {
+        snippet.shuffle_seed = shuffle.then(rand::random);
+    }
+    if let Some(seed) = snippet.shuffle_seed.as_mut() {
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(seed).prefix("Seed: "));
+            if ui.button("🎲 Re-roll").clicked() {
+                *seed = rand::random();
+            }
+        });
+    }
     
+    ui.horizontal(|ui| {
+        ui.label("Author: ");
+        ui.text_edit_singleline(&mut snippet.author);
+    });
+    ui.collapsing("Notes", |ui| {
+        ui.text_edit_multiline(&mut snippet.notes);
+    });
+
     ui.group(|ui| {
         ui.heading("Tags");
         if ui.button("Add Tag").clicked() {
@@ -25,10 +54,130 @@ fn render_snippet_settings(snippet: &mut Snippets, ui: &mut egui::Ui) {
         for &idx in tags_to_remove.iter().rev() {
             snippet.tags.remove(idx);
         }
+
+        let suggestions = patterns.suggestions(snippet, pattern::SUGGEST_THRESHOLD);
+        if !suggestions.is_empty() {
+            ui.label("Suggested:");
+            ui.horizontal_wrapped(|ui| {
+                for suggestion in suggestions {
+                    let label = format!("+ {} ({:.0}%)", suggestion.pattern.tag(), suggestion.confidence * 100.0);
+                    if ui.button(label).on_hover_text(suggestion.pattern.label()).clicked() {
+                        snippet.tags.push(suggestion.pattern.tag().to_string());
+                    }
+                }
+            });
+        }
+    });
+}
+
+fn render_transform_controls(app_state: &mut AppState, selected_idx: usize, ui: &mut egui::Ui) {
+    let inputs = &mut app_state.transform_inputs;
+    let Some(snippet) = app_state.snippets.get_mut(selected_idx) else {
+        return;
+    };
+    let mut message = None;
+
+    ui.group(|ui| {
+        ui.heading("Column Transforms");
+        let mut to_remove = None;
+        for (idx, t) in snippet.transforms.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}. {}", idx + 1, t.label()));
+                if ui.button("❌").clicked() {
+                    to_remove = Some(idx);
+                }
+            });
+        }
+        if let Some(idx) = to_remove {
+            snippet.transforms.remove(idx);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Mirror").clicked() {
+                snippet.transforms.push(ColumnTransform::Mirror);
+            }
+            if ui.button("Hand swap").clicked() {
+                snippet.transforms.push(ColumnTransform::HandSwap);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut inputs.rotate).range(-17..=17));
+            if ui.button("Rotate").clicked() {
+                snippet.transforms.push(ColumnTransform::Rotate(inputs.rotate));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut inputs.permutation);
+            if ui.button("Permute").clicked() {
+                match ColumnTransform::parse_permutation(&inputs.permutation, snippet.keycount) {
+                    Some(t) => snippet.transforms.push(t),
+                    None => message = Some(format!("Invalid permutation for {}K", snippet.keycount)),
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut inputs.seed).prefix("Seed: "));
+            if ui.button("Random").clicked() {
+                snippet.transforms.push(ColumnTransform::RandomSeeded(inputs.seed));
+            }
+            if ui.button("No jacks").clicked() {
+                snippet.transforms.push(ColumnTransform::NoJacks(inputs.seed));
+            }
+        });
     });
+
+    if let Some(message) = message {
+        app_state.show_notification(message);
+    }
 }
 
-fn render_snippet_info(snippet: &Snippets, speed: f64, ui: &mut egui::Ui) {
+fn render_rhythm_controls(app_state: &mut AppState, ui: &mut egui::Ui) {
+    let mut transform = None;
+    let inputs = &mut app_state.transform_inputs;
+    ui.group(|ui| {
+        ui.heading("Rhythm Transforms");
+        ui.label("Each transform creates a new snippet");
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut inputs.stretch_ratio).range(0.1..=4.0).speed(0.01).prefix("x"));
+            if ui.button("Stretch").clicked() {
+                transform = Some(RhythmTransform::Stretch(inputs.stretch_ratio));
+            }
+            if ui.button("1/4 → 1/3").clicked() {
+                transform = Some(RhythmTransform::Stretch(4.0 / 3.0));
+            }
+            if ui.button("1/3 → 1/4").clicked() {
+                transform = Some(RhythmTransform::Stretch(3.0 / 4.0));
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Reverse").clicked() {
+                transform = Some(RhythmTransform::Reverse);
+            }
+            if ui.button("Half density").clicked() {
+                transform = Some(RhythmTransform::HalveDensity);
+            }
+            if ui.button("Double density").clicked() {
+                transform = Some(RhythmTransform::DoubleDensity);
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("LN → rice").clicked() {
+                transform = Some(RhythmTransform::HoldsToRice);
+            }
+            ui.add(egui::DragValue::new(&mut inputs.ln_length).range(10.0..=5000.0).suffix(" ms"));
+            if ui.button("Rice → LN").clicked() {
+                transform = Some(RhythmTransform::RiceToHolds(inputs.ln_length));
+            }
+        });
+    });
+
+    if let Some(transform) = transform {
+        app_state.apply_rhythm_transform(transform);
+    }
+}
+
+/// Renvoie true si "Jump to source" a été cliqué.
+fn render_snippet_info(snippet: &Snippets, speed: f64, ui: &mut egui::Ui) -> bool {
     ui.heading(egui::RichText::new(&snippet.name).size(24.0).strong());
     
     if !snippet.tags.is_empty() {
@@ -47,7 +196,79 @@ fn render_snippet_info(snippet: &Snippets, speed: f64, ui: &mut egui::Ui) {
     ui.group(|ui| {
         ui.label(format!("Number of hit objects: {}", snippet.hit_objects.len()));
         ui.label(format!("Key count: {}", snippet.keycount));
-        ui.label(format!("Bpm: {}", (60000.0/snippet.timing_points.beat_len)*speed));
+        if !snippet.author.is_empty() {
+            ui.label(format!("Author: {}", snippet.author));
+        }
+        ui.label(format!("Timing points: {} ({} SV)", snippet.timing_points.len(), snippet.difficulty_points.len()));
+        let hitsounded = snippet.hit_objects.iter().filter(|h| utils::hit_sound_type(&h.samples) != 0).count();
+        ui.label(format!("Hitsounds: {} notes, {} sample points", hitsounded, snippet.sample_points.len()));
+        if snippet.timing_points.is_empty() {
+            ui.label("Bpm: unknown");
+        } else {
+            ui.label(format!("Bpm: {}", (60000.0/snippet.base_beat_len())*speed));
+        }
+    });
+
+    let mut jump = false;
+    let source = &snippet.source;
+    if source.is_known() {
+        ui.group(|ui| {
+            ui.heading("Source");
+            ui.label(source.label());
+            if source.beatmap_id != 0 {
+                ui.label(format!("Beatmap {} (set {})", source.beatmap_id, source.beatmapset_id));
+            }
+            ui.label(format!("{}ms - {}ms at {:.0} bpm", source.start, source.end, source.bpm));
+            jump = ui.button("Jump to source").clicked();
+        });
+    }
+    jump
+}
+
+fn render_source_controls(app_state: &mut AppState, ui: &mut egui::Ui) {
+    ui.group(|ui| {
+        ui.heading("Game Source");
+        let sources = &mut app_state.sources;
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(sources.memory.is_some(), |ui| {
+                ui.radio_value(&mut sources.use_file, false, "osu! (memory)");
+            });
+            ui.radio_value(&mut sources.use_file, true, "File");
+        });
+        if sources.memory.is_none() {
+            if sources.is_attaching() {
+                ui.label("Waiting for osu!...");
+            } else if ui.button("🔌 Reconnect to osu!").clicked() {
+                sources.reconnect();
+            }
+        }
+
+        if sources.use_file {
+            ui.horizontal(|ui| {
+                ui.label(".osu: ");
+                ui.text_edit_singleline(&mut sources.file.beatmap_path);
+            });
+            ui.add(egui::DragValue::new(&mut sources.file.time).range(0..=i32::MAX).suffix(" ms").prefix("Time: "));
+        }
+
+        let source = sources.active();
+        match source.beatmap_path() {
+            Ok(path) => {
+                let file_name = path.file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                ui.label(format!("Beatmap: {}", file_name));
+            }
+            Err(e) => {
+                ui.label(format!("Beatmap: {}", e));
+            }
+        }
+        if let Ok(time) = source.current_time() {
+            ui.label(format!("Time: {}ms", time));
+        }
+        if let Ok(mods) = source.mods() {
+            ui.label(format!("Mods: {}", mods));
+        }
     });
 }
 
@@ -55,36 +276,63 @@ fn render_creation_controls(app_state: &mut AppState, ui: &mut egui::Ui) {
     ui.group(|ui| {
         ui.heading("New Snippet Controls");
         if ui.button("Set Next").clicked() {
-            let result = app_state.snippets_maker.set_next(&app_state.process, &mut app_state.state.lock().unwrap());
-            let message = match result {
-                Ok(NextUpdate::TimeStart) => format!("End at {}", app_state.snippets_maker.time_end),
-                Ok(NextUpdate::TimeEnd) => format!("Start at {}", app_state.snippets_maker.time_start),
-                Err(e) => format!("Error: {}", e),
-            };
-            app_state.show_notification(message);
+            app_state.mark_next();
         }
         if ui.button("Create New Snippet").clicked() {
-            let snippets_maker = app_state.snippets_maker.clone();
-            let mut new_snippets = Snippets::new();
-            let creation_result = new_snippets.load_snippets_memory_path(
-                &app_state.process, 
-                &mut app_state.state.lock().unwrap(), 
-                &snippets_maker
-            );
-            
-            match creation_result {
-                Ok(_) => {
-                    new_snippets.name = format!("New Snippet {}", app_state.snippets.len() + 1);
-                    app_state.snippets.push(new_snippets);
-                    app_state.selected_snippet = Some(app_state.snippets.len() - 1);
-                    app_state.start_time = std::time::Instant::now();
-                    app_state.show_notification("New snippet created".to_string());
+            app_state.create_snippet();
+        }
+        if ui.button("📋 Paste as new snippet").clicked() {
+            app_state.open_paste_dialog();
+        }
+    });
+}
+
+fn render_insert_options(app_state: &mut AppState, ui: &mut egui::Ui) {
+    let options = &mut app_state.insert_options;
+    ui.group(|ui| {
+        ui.heading("Insert Options");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut options.snap_placement, "Snap placement to");
+            egui::ComboBox::from_id_salt("placement_divisor")
+                .selected_text(format!("1/{}", options.placement_divisor))
+                .show_ui(ui, |ui| {
+                    for divisor in timing::SNAP_DIVISORS {
+                        ui.selectable_value(&mut options.placement_divisor, divisor, format!("1/{}", divisor));
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut options.snap_objects, "Snap notes up to");
+            egui::ComboBox::from_id_salt("object_divisor")
+                .selected_text(format!("1/{}", options.object_divisor))
+                .show_ui(ui, |ui| {
+                    for divisor in timing::SNAP_DIVISORS {
+                        ui.selectable_value(&mut options.object_divisor, divisor, format!("1/{}", divisor));
+                    }
+                });
+        });
+        egui::ComboBox::from_label("On conflict")
+            .selected_text(options.conflict_mode.label())
+            .show_ui(ui, |ui| {
+                for mode in ConflictMode::ALL {
+                    ui.selectable_value(&mut options.conflict_mode, mode, mode.label());
                 }
-                Err(_) => {
-                    app_state.show_notification("Failed to create snippet".to_string());
+            });
+        ui.add(egui::Slider::new(&mut options.min_gap, 0.0..=100.0).text("Min gap (ms)"));
+        egui::ComboBox::from_label("Keycount conversion")
+            .selected_text(options.keycount_strategy.label())
+            .show_ui(ui, |ui| {
+                for strategy in KeycountStrategy::ALL {
+                    ui.selectable_value(&mut options.keycount_strategy, strategy, strategy.label());
                 }
-            }
-        }
+            });
+        egui::ComboBox::from_label("Hitsounds")
+            .selected_text(options.hitsound_mode.label())
+            .show_ui(ui, |ui| {
+                for mode in HitsoundMode::ALL {
+                    ui.selectable_value(&mut options.hitsound_mode, mode, mode.label());
+                }
+            });
     });
 }
 
@@ -97,8 +345,22 @@ fn render_playback_controls(app_state: &mut AppState, ui: &mut egui::Ui) {
 }
 
 fn render_snippet_controls(app_state: &mut AppState, ui: &mut egui::Ui) {
+    let stale = app_state.selected_snippet
+        .and_then(|idx| app_state.snippets.get(idx))
+        .is_some_and(|s| s.stale);
     ui.group(|ui| {
         ui.heading("Snippet Controls");
+        if stale {
+            ui.colored_label(egui::Color32::from_rgb(255, 200, 0), "⚠ The file changed on disk");
+            ui.horizontal(|ui| {
+                if ui.button("Reload").clicked() {
+                    app_state.reload_selected();
+                }
+                if ui.button("Keep mine").clicked() {
+                    app_state.keep_selected();
+                }
+            });
+        }
         ui.horizontal(|ui| {
             if ui.button("Save Snippet").clicked() {
                 app_state.show_save_dialog = true;
@@ -106,47 +368,185 @@ fn render_snippet_controls(app_state: &mut AppState, ui: &mut egui::Ui) {
             if let Some(selected_idx) = app_state.selected_snippet {
                 if let Some(snippet) = app_state.snippets.get(selected_idx) {
                     let is_saved = snippet.is_saved;
-                    let name = snippet.name.clone();
-                    if is_saved && ui.button("Update").clicked() {
-                        if let Some(snippet) = app_state.snippets.get_mut(selected_idx) {
-                            if let Ok(_) = snippet.save_snippets_to_file(&name) {
-                                app_state.show_notification(format!("Updated {}", name));
-                            }
-                        }
+                    if is_saved && snippet.file_path.is_some()
+                        && ui.add_enabled(!stale, egui::Button::new("Update"))
+                            .on_disabled_hover_text("Reload or keep your version first")
+                            .clicked()
+                    {
+                        app_state.update_selected();
                     }
                 }
             }
             if ui.button("Insert to Beatmap").clicked() {
-                if let Some(selected_idx) = app_state.selected_snippet {
-                    if let Some(snippet) = app_state.snippets.get_mut(selected_idx) {
-                        let name = snippet.name.clone();
-                        snippet.insert_snippets_to_beatmap(&app_state.process, &mut app_state.state.lock().unwrap());
-                        app_state.show_notification(format!("Successfully inserted {}", name));
-                    }
+                app_state.prepare_selected_insert();
+            }
+        });
+        if ui.button("📋 Copy to clipboard").clicked() {
+            if let Some(snippet) = app_state.selected_snippet.and_then(|idx| app_state.snippets.get(idx)) {
+                ui.ctx().copy_text(snippet.to_clipboard_text());
+                let message = format!("Copied {} objects", snippet.hit_objects.len());
+                app_state.show_notification(message);
+            }
+        }
+    });
+}
+
+fn render_library_controls(app_state: &mut AppState, ui: &mut egui::Ui) {
+    ui.collapsing("Libraries", |ui| {
+        let mut to_remove = None;
+        for (idx, root) in app_state.settings.libraries.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(root.label());
+                if ui.button("❌").clicked() {
+                    to_remove = Some(idx);
                 }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut app_state.library_input);
+            egui::ComboBox::from_id_salt("library_kind")
+                .selected_text(app_state.library_kind_input.label())
+                .show_ui(ui, |ui| {
+                    for kind in LibraryKind::ALL {
+                        ui.selectable_value(&mut app_state.library_kind_input, kind, kind.label());
+                    }
+                });
+            if ui.button("Add").clicked() {
+                app_state.add_library();
+            }
+        });
+        if let Some(idx) = to_remove {
+            app_state.remove_library(idx);
+        }
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut app_state.auto_tag_threshold, 0.1..=1.0).text("Min confidence"));
+            let label = if app_state.is_auto_tagging() { "Tagging..." } else { "🏷 Auto-tag library" };
+            if ui.add_enabled(!app_state.is_auto_tagging(), egui::Button::new(label)).clicked() {
+                app_state.auto_tag_library();
             }
         });
     });
 }
 
+fn render_hotkey_controls(app_state: &mut AppState, ui: &mut egui::Ui) {
+    ui.collapsing("Hotkeys", |ui| {
+        for (action, input) in HotkeyAction::ALL.iter().zip(app_state.hotkey_inputs.iter_mut()) {
+            ui.horizontal(|ui| {
+                ui.label(action.label());
+                ui.text_edit_singleline(input);
+            });
+        }
+        if ui.button("Save Hotkeys").clicked() {
+            app_state.apply_hotkeys();
+        }
+    });
+}
+
+fn render_history_controls(app_state: &mut AppState, ui: &mut egui::Ui) {
+    ui.group(|ui| {
+        ui.heading("Insert History");
+        if app_state.history.records.is_empty() {
+            ui.label("No insert yet");
+            return;
+        }
+        if ui.button("Revert last insert").on_hover_text("Reverts the last insert into the map open in the game source").clicked() {
+            let reverted = app_state
+                .sources
+                .active()
+                .beatmap_path()
+                .and_then(|path| app_state.history.revert_last(&path));
+            let message = match reverted {
+                Ok(record) => format!("Reverted {} in {}", record.snippet_name, record.beatmap_path.display()),
+                Err(e) => format!("Error: {}", e),
+            };
+            app_state.show_notification(message);
+        }
+
+        let mut restore_idx = None;
+        let mut reuse = None;
+        egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+            for (idx, record) in app_state.history.records.iter().enumerate().rev() {
+                ui.horizontal(|ui| {
+                    let mut hover = record.beatmap_path.file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    for transform in &record.transforms {
+                        hover.push_str(&format!("\n{}", transform.label()));
+                    }
+                    if let Some(seed) = record.shuffle_seed {
+                        hover.push_str(&format!("\nShuffle seed: {}", seed));
+                    }
+                    ui.label(format!("{} @ {}ms ({} objects)", record.snippet_name, record.placement_time, record.object_count))
+                        .on_hover_text(hover);
+                    if ui.button("Restore").clicked() {
+                        restore_idx = Some(idx);
+                    }
+                    let transformed = record.shuffle_seed.is_some() || !record.transforms.is_empty();
+                    if transformed && ui.button("Reuse transforms").clicked() {
+                        reuse = Some((record.transforms.clone(), record.shuffle_seed));
+                    }
+                });
+            }
+        });
+
+        if let Some((transforms, seed)) = reuse {
+            if let Some(snippet) = app_state.selected_snippet.and_then(|idx| app_state.snippets.get_mut(idx)) {
+                snippet.transforms = transforms;
+                snippet.shuffle_seed = seed;
+                let message = format!("Using {} transforms of the insert", snippet.transforms.len() + seed.is_some() as usize);
+                app_state.show_notification(message);
+            }
+        }
+
+        if let Some(idx) = restore_idx {
+            let message = match app_state.history.restore(idx) {
+                Ok(record) => format!("Restored {} to before {}", record.beatmap_path.display(), record.snippet_name),
+                Err(e) => format!("Error: {}", e),
+            };
+            app_state.show_notification(message);
+        }
+    });
+}
+
 pub fn render_right_panel(app_state: &mut AppState, ctx: &egui::Context) {
     egui::SidePanel::right("info_panel").show(ctx, |ui| {
-        render_creation_controls(app_state, ui);
-        ui.add_space(8.0);
-
-        if let Some(selected_idx) = app_state.selected_snippet {
-            render_snippet_controls(app_state, ui);
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            render_source_controls(app_state, ui);
+            ui.add_space(8.0);
+            render_creation_controls(app_state, ui);
             ui.add_space(8.0);
-            
-            if let Some(snippet) = app_state.snippets.get_mut(selected_idx) {
-                render_snippet_info(snippet, app_state.snippet_speed as f64, ui);
+            render_history_controls(app_state, ui);
+            ui.add_space(8.0);
+            render_hotkey_controls(app_state, ui);
+            ui.add_space(8.0);
+            render_library_controls(app_state, ui);
+            ui.add_space(8.0);
+
+            if let Some(selected_idx) = app_state.selected_snippet {
+                render_snippet_controls(app_state, ui);
                 ui.add_space(8.0);
-                render_snippet_settings(snippet, ui);
+                render_insert_options(app_state, ui);
                 ui.add_space(8.0);
-                render_playback_controls(app_state, ui);
+
+                if let Some(snippet) = app_state.snippets.get_mut(selected_idx) {
+                    let jump = render_snippet_info(snippet, app_state.snippet_speed as f64, ui);
+                    ui.add_space(8.0);
+                    render_snippet_settings(snippet, &mut app_state.pattern_cache, ui);
+                    if jump {
+                        app_state.jump_to_source(ui.ctx());
+                    }
+                    ui.add_space(8.0);
+                    render_transform_controls(app_state, selected_idx, ui);
+                    ui.add_space(8.0);
+                    render_rhythm_controls(app_state, ui);
+                    ui.add_space(8.0);
+                    render_playback_controls(app_state, ui);
+                }
             }
-        }
+        });
     });
 
     save_dialog::render_save_dialog(app_state, ctx);
+    insert_dialog::render_insert_dialog(app_state, ctx);
+    paste_dialog::render_paste_dialog(app_state, ctx);
 } 
\ No newline at end of file
@@ -11,6 +11,14 @@ pub fn render_save_dialog(app_state: &mut AppState, ctx: &egui::Context) {
                     ui.label("Filename: ");
                     ui.text_edit_singleline(&mut app_state.save_filename);
                 });
+                let roots = &app_state.settings.libraries;
+                egui::ComboBox::from_label("Library")
+                    .selected_text(roots.get(app_state.save_root).map(|r| r.label()).unwrap_or_default())
+                    .show_ui(ui, |ui| {
+                        for (idx, root) in roots.iter().enumerate().filter(|(_, r)| r.kind.is_writable()) {
+                            ui.selectable_value(&mut app_state.save_root, idx, root.label());
+                        }
+                    });
                 ui.horizontal(|ui| {
                     if ui.button("Save").clicked() && !app_state.save_filename.is_empty() {
                         let filename = if !app_state.save_filename.ends_with(".snippets") {
@@ -18,14 +26,9 @@ pub fn render_save_dialog(app_state: &mut AppState, ctx: &egui::Context) {
                         } else {
                             app_state.save_filename.clone()
                         };
-                        if let Some(selected_idx) = app_state.selected_snippet {
-                            if let Some(snippet) = app_state.snippets.get_mut(selected_idx) {
-                                snippet.save_snippets_to_file(&filename);
-                                app_state.show_notification(format!("Successfully saved {}", filename));
-                                app_state.show_save_dialog = false;
-                                app_state.save_filename.clear();
-                            }
-                        }
+                        app_state.save_selected(&filename);
+                        app_state.show_save_dialog = false;
+                        app_state.save_filename.clear();
                     }
                     if ui.button("Cancel").clicked() {
                         app_state.show_save_dialog = false;
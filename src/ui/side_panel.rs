@@ -1,17 +1,25 @@
-use std::fs;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use egui;
-use crate::ui::app_state::{AppState, UnloadedSnippet};
+use crate::ui::app_state::AppState;
+use crate::snippets::index::IndexEntry;
+use crate::snippets::search::{self, SearchHit, SearchQuery};
 use crate::snippets::structs::Snippets;
+use crate::snippets::tags::{self, TagNode};
 
 #[derive(Default)]
 struct SidebarAction {
     select_snippet: Option<usize>,
-    load_file: Option<String>,
+    load_file: Option<PathBuf>,
+    export_pack: bool,
+    import_pack: bool,
+    rename_tag: bool,
 }
 
 fn render_snippet_entry(snippet: &Snippets, index: usize, is_selected: bool, ui: &mut egui::Ui) -> Option<usize> {
-    let mut label = if snippet.is_saved {
+    let mut label = if snippet.stale {
+        format!("{} ⚠", snippet.name)
+    } else if snippet.is_saved {
         format!("{} 💾", snippet.name)
     } else {
         format!("{} ⚡", snippet.name)
@@ -29,108 +37,215 @@ fn render_snippet_entry(snippet: &Snippets, index: usize, is_selected: bool, ui:
     }
 }
 
+fn index_entry_label(entry: &IndexEntry) -> String {
+    let mut label = format!(
+        "{} · {}K · {} notes · {:.0} bpm",
+        entry.name, entry.keycount, entry.object_count, entry.bpm
+    );
+    if !entry.tags.is_empty() {
+        label.push_str(&format!(" ({})", entry.tags.join(", ")));
+    }
+    label
+}
+
+/// Les snippets à ranger dans l'arbre des tags.
+struct TagListing<'a> {
+    snippets: &'a [Snippets],
+    unloaded: &'a [&'a IndexEntry],
+    selected: Option<usize>,
+}
+
+impl TagListing<'_> {
+    /// Une snippet apparaît sous chacun de ses tags, le compteur inclut les sous-tags.
+    fn render_node(&self, node: &TagNode, action: &mut SidebarAction, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new(format!("{} ({})", node.name(), node.count))
+            .id_salt(&node.path)
+            .show(ui, |ui| {
+                for child in node.children.values() {
+                    self.render_node(child, action, ui);
+                }
+                self.render_entries(|tags| tags.iter().any(|t| t.eq_ignore_ascii_case(&node.path)), action, ui);
+            });
+    }
+
+    fn render_entries(&self, filter: impl Fn(&[String]) -> bool, action: &mut SidebarAction, ui: &mut egui::Ui) {
+        for (index, snippet) in self.snippets.iter().enumerate().filter(|(_, s)| filter(s.tags.as_slice())) {
+            if let Some(idx) = render_snippet_entry(snippet, index, self.selected == Some(index), ui) {
+                action.select_snippet = Some(idx);
+            }
+        }
+        for unloaded in self.unloaded.iter().filter(|u| filter(u.tags.as_slice())) {
+            if ui.selectable_label(false, index_entry_label(unloaded)).clicked() {
+                action.load_file = Some(unloaded.path.clone());
+            }
+        }
+    }
+}
+
 pub fn render_side_panel(app_state: &mut AppState, ctx: &egui::Context) {
     let mut action = SidebarAction::default();
 
     egui::SidePanel::left("snippets_list").show(ctx, |ui| {
         ui.heading("Snippets");
+        ui.add(
+            egui::TextEdit::singleline(&mut app_state.search_text)
+                .hint_text("🔍 js k:4 bpm:170-190 nps:>10 ln:no tag:a|b -tag:c"),
+        );
+        let query = SearchQuery::parse(&app_state.search_text);
+        if !query.is_empty() {
+            let hits = search::search(&query, &app_state.snippets, &app_state.unloaded_snippets);
+            if hits.is_empty() {
+                ui.label("No result");
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (hit, _) in hits {
+                    match hit {
+                        SearchHit::Loaded(index) => {
+                            let snippet = &app_state.snippets[index];
+                            if let Some(idx) = render_snippet_entry(snippet, index, app_state.selected_snippet == Some(index), ui) {
+                                action.select_snippet = Some(idx);
+                            }
+                        }
+                        SearchHit::Unloaded(index) => {
+                            let entry = &app_state.unloaded_snippets[index];
+                            if ui.selectable_label(false, index_entry_label(entry)).clicked() {
+                                action.load_file = Some(entry.path.clone());
+                            }
+                        }
+                    }
+                }
+            });
+            return;
+        }
         
-        let mut tagged_snippets: HashMap<String, Vec<(usize, &Snippets)>> = HashMap::new();
-        let mut untagged_snippets = Vec::new();
         let selected_idx = app_state.selected_snippet;
-
         for (index, snippet) in app_state.snippets.iter().enumerate() {
-            if snippet.tags.is_empty() {
-                untagged_snippets.push((index, snippet));
-            } else if let Some(first_tag) = snippet.tags.first() {
-                tagged_snippets.entry(first_tag.clone())
-                    .or_default()
-                    .push((index, snippet));
+            if let Some(idx) = render_snippet_entry(snippet, index, selected_idx == Some(index), ui) {
+                action.select_snippet = Some(idx);
             }
         }
 
-        // Sort tags alphabetically
-        let mut sorted_tags: Vec<_> = tagged_snippets.keys().collect();
-        sorted_tags.sort();
+        ui.separator();
+        ui.heading("Tags");
+
+        // Un fichier déjà chargé n'apparaît qu'une fois, sous sa version chargée
+        let unloaded: Vec<&IndexEntry> = app_state.unloaded_snippets
+            .iter()
+            .filter(|u| !app_state.snippets.iter().any(|s| s.file_path.as_ref() == Some(&u.path)))
+            .collect();
+        let tree = tags::build_tree(
+            app_state.snippets.iter().map(|s| s.tags.as_slice())
+                .chain(unloaded.iter().map(|u| u.tags.as_slice())),
+        );
+        let listing = TagListing {
+            snippets: &app_state.snippets,
+            unloaded: &unloaded,
+            selected: selected_idx,
+        };
+        for node in tree.children.values() {
+            listing.render_node(node, &mut action, ui);
+        }
 
-        for tag in sorted_tags {
-            if let Some(snippets) = tagged_snippets.get(tag) {
-                ui.collapsing(tag, |ui| {
-                    for &(index, snippet) in snippets {
-                        if let Some(idx) = render_snippet_entry(snippet, index, selected_idx == Some(index), ui) {
-                            action.select_snippet = Some(idx);
-                        }
-                    }
-                });
-            }
+        let untagged_count = app_state.snippets.iter().filter(|s| s.tags.is_empty()).count()
+            + unloaded.iter().filter(|u| u.tags.is_empty()).count();
+        if untagged_count > 0 {
+            ui.collapsing(format!("Untagged ({})", untagged_count), |ui| {
+                listing.render_entries(|tags| tags.is_empty(), &mut action, ui);
+            });
         }
 
-        if !untagged_snippets.is_empty() {
-            ui.collapsing("Untagged", |ui| {
-                for (index, snippet) in untagged_snippets {
-                    if let Some(idx) = render_snippet_entry(snippet, index, selected_idx == Some(index), ui) {
-                        action.select_snippet = Some(idx);
+        let tag_paths = tree.paths();
+        ui.collapsing("Rename / merge tags", |ui| {
+            egui::ComboBox::from_label("From")
+                .selected_text(app_state.tag_rename_from.as_str())
+                .show_ui(ui, |ui| {
+                    for path in &tag_paths {
+                        ui.selectable_value(&mut app_state.tag_rename_from, path.to_string(), *path);
                     }
-                }
+                });
+            ui.horizontal(|ui| {
+                ui.label("To: ");
+                ui.add(egui::TextEdit::singleline(&mut app_state.tag_rename_to).hint_text("ln/inverse"));
             });
-        }
+            if tag_paths.contains(&app_state.tag_rename_to.trim()) {
+                ui.label(format!("{} already exists, the tags will be merged", app_state.tag_rename_to.trim()));
+            }
+            action.rename_tag = ui.button("🏷 Rename / merge").clicked();
+        });
 
         ui.separator();
         ui.heading("Load from file");
         
-        let mut unloaded_by_tag: HashMap<String, Vec<&UnloadedSnippet>> = HashMap::new();
-        let mut untagged_unloaded = Vec::new();
-        
+        // Une section par bibliothèque, les sous-dossiers servent de catégories
+        let mut unloaded_by_category: BTreeMap<(usize, &str), Vec<&IndexEntry>> = BTreeMap::new();
         for unloaded in &app_state.unloaded_snippets {
-            let already_loaded = app_state.snippets.iter().any(|s| s.name == unloaded.name);
+            let already_loaded = app_state.snippets.iter().any(|s| s.file_path.as_ref() == Some(&unloaded.path));
             if !already_loaded {
-                if unloaded.tags.is_empty() {
-                    untagged_unloaded.push(unloaded);
-                } else if let Some(first_tag) = unloaded.tags.first() {
-                    unloaded_by_tag.entry(first_tag.clone())
-                        .or_default()
-                        .push(unloaded);
-                }
+                unloaded_by_category.entry((unloaded.root, unloaded.category.as_str()))
+                    .or_default()
+                    .push(unloaded);
             }
         }
 
-        // Sort unloaded tags alphabetically
-        let mut sorted_unloaded_tags: Vec<_> = unloaded_by_tag.keys().collect();
-        sorted_unloaded_tags.sort();
-
-        for tag in sorted_unloaded_tags {
-            if let Some(snippets) = unloaded_by_tag.get(tag) {
-                ui.collapsing(tag, |ui| {
-                    for unloaded in snippets {
-                        if ui.selectable_label(false, &unloaded.name).clicked() {
-                            action.load_file = Some(unloaded.name.clone());
+        for (idx, root) in app_state.settings.libraries.iter().enumerate() {
+            ui.collapsing(root.label(), |ui| {
+                for ((_, category), snippets) in unloaded_by_category.range((idx, "")..(idx + 1, "")) {
+                    let mut render_entries = |ui: &mut egui::Ui| {
+                        for unloaded in snippets {
+                            if ui.selectable_label(false, index_entry_label(unloaded)).clicked() {
+                                action.load_file = Some(unloaded.path.clone());
+                            }
                         }
+                    };
+                    if category.is_empty() {
+                        render_entries(ui);
+                    } else {
+                        ui.collapsing(*category, render_entries);
                     }
-                });
-            }
+                }
+            });
         }
 
-        if !untagged_unloaded.is_empty() {
-            ui.collapsing("Untagged", |ui| {
-                for unloaded in untagged_unloaded {
-                    if ui.selectable_label(false, &unloaded.name).clicked() {
-                        action.load_file = Some(unloaded.name.clone());
-                    }
+        ui.separator();
+        ui.heading("Packs");
+        ui.horizontal(|ui| {
+            ui.label("File: ");
+            ui.text_edit_singleline(&mut app_state.pack_path);
+        });
+        egui::ComboBox::from_label("Export")
+            .selected_text(if app_state.pack_tag.is_empty() { "Selected snippet" } else { app_state.pack_tag.as_str() })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut app_state.pack_tag, String::new(), "Selected snippet");
+                for path in &tag_paths {
+                    ui.selectable_value(&mut app_state.pack_tag, path.to_string(), *path);
                 }
             });
-        }
+        ui.horizontal(|ui| {
+            action.export_pack = ui.button("📦 Export pack").clicked();
+            action.import_pack = ui.button("📥 Import pack").clicked();
+        });
     });
 
     // Apply actions after all borrows are done
+    if action.export_pack {
+        app_state.export_pack();
+    }
+    if action.import_pack {
+        app_state.import_pack();
+    }
+    if action.rename_tag {
+        app_state.rename_tag();
+    }
+
     if let Some(index) = action.select_snippet {
         app_state.selected_snippet = Some(index);
         app_state.start_time = std::time::Instant::now();
     }
     
-    if let Some(filename) = action.load_file {
+    if let Some(path) = action.load_file {
         let mut snippets = Snippets::new();
-        if let Ok(_) = snippets.load_snippets(&filename) {
-            snippets.name = filename;
+        if let Ok(_) = snippets.load_snippets(&path) {
+            snippets.name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
             app_state.snippets.push(snippets);
             app_state.selected_snippet = Some(app_state.snippets.len() - 1);
             app_state.start_time = std::time::Instant::now();
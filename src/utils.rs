@@ -1,20 +1,376 @@
-use rosu_map::section::hit_objects::{HitObject, HitObjectKind};
+use rosu_map::section::hit_objects::{HitObject, HitObjectKind, HitObjectSlider, SplineType};
+use rosu_map::section::hit_objects::hit_samples::{HitSampleDefaultName, HitSampleInfo, HitSampleInfoName, SampleBank};
 
+// Bits du champ type d'une ligne [HitObjects]
+const TYPE_CIRCLE: i32 = 1 << 0;
+const TYPE_SLIDER: i32 = 1 << 1;
+const TYPE_NEW_COMBO: i32 = 1 << 2;
+const TYPE_SPINNER: i32 = 1 << 3;
+const TYPE_HOLD: i32 = 1 << 7;
 
-pub fn hit_object_to_string(hit_object: HitObject) -> String {
-    match hit_object.kind {
+fn sample_bank_id(bank: &SampleBank) -> i32 {
+    match bank {
+        SampleBank::None => 0,
+        SampleBank::Normal => 1,
+        SampleBank::Soft => 2,
+        SampleBank::Drum => 3,
+    }
+}
+
+/// Bits whistle/finish/clap du champ hitSound.
+pub fn hit_sound_type(samples: &[HitSampleInfo]) -> u8 {
+    samples.iter().fold(0, |acc, sample| match &sample.name {
+        HitSampleInfoName::Default(HitSampleDefaultName::Whistle) => acc | 1 << 1,
+        HitSampleInfoName::Default(HitSampleDefaultName::Finish) => acc | 1 << 2,
+        HitSampleInfoName::Default(HitSampleDefaultName::Clap) => acc | 1 << 3,
+        _ => acc,
+    })
+}
+
+fn normal_sample(samples: &[HitSampleInfo]) -> Option<&HitSampleInfo> {
+    samples
+        .iter()
+        .find(|s| matches!(s.name, HitSampleInfoName::Default(HitSampleDefaultName::Normal)))
+}
+
+/// `normalSet:additionSet`, utilisé seul pour les edgeSets des sliders.
+/// Une bank héritée s'écrit 0, sinon elle serait relue comme explicite.
+fn sample_sets(samples: &[HitSampleInfo]) -> String {
+    let normal_set = normal_sample(samples)
+        .filter(|s| s.bank_specified)
+        .map(|s| sample_bank_id(&s.bank))
+        .unwrap_or(0);
+    let addition_set = samples
+        .iter()
+        .find(|s| matches!(
+            s.name,
+            HitSampleInfoName::Default(HitSampleDefaultName::Whistle | HitSampleDefaultName::Finish | HitSampleDefaultName::Clap)
+        ))
+        .filter(|s| s.bank_specified)
+        .map(|s| sample_bank_id(&s.bank))
+        .filter(|&bank| bank != normal_set)
+        .unwrap_or(0);
+    format!("{}:{}", normal_set, addition_set)
+}
+
+/// Champ hitSample `normalSet:additionSet:index:volume:filename`.
+/// Pour un LN c'est le même sample pour la tête et la queue, le format n'en stocke qu'un.
+pub fn hit_sample_string(samples: &[HitSampleInfo]) -> String {
+    let file = samples.iter().find_map(|s| match &s.name {
+        HitSampleInfoName::File(filename) => Some((s, filename.as_str())),
+        _ => None,
+    });
+    let first = file.map(|(s, _)| s).or(normal_sample(samples)).or(samples.first());
+    // Un sample fichier n'a pas d'index à lui, les additions gardent celui de la ligne
+    let index = samples
+        .iter()
+        .find(|s| !matches!(s.name, HitSampleInfoName::File(_)))
+        .or(first)
+        .map(|s| s.custom_sample_bank)
+        .unwrap_or(0);
+    let volume = first.map(|s| s.volume).unwrap_or(0);
+    let filename = file.map(|(_, f)| f).unwrap_or("");
+
+    format!("{}:{}:{}:{}", sample_sets(samples), index, volume, filename)
+}
+
+/// Bits de type communs : new combo et combo offset (3 bits à partir du bit 4).
+fn type_bits(kind: i32, new_combo: bool, combo_offset: i32) -> i32 {
+    let mut bits = kind;
+    if new_combo {
+        bits |= TYPE_NEW_COMBO;
+    }
+    bits | ((combo_offset & 0b111) << 4)
+}
+
+/// `curveType|x:y|...,slides,length,edgeSounds,edgeSets` comme l'encodeur de lazer :
+/// les points sont relatifs au slider dans rosu-map, on les remet en absolu, et un
+/// segment du même type que le précédent est écrit en dupliquant son premier point.
+fn slider_params(slider: &HitObjectSlider) -> String {
+    let (x, y) = (slider.pos.x, slider.pos.y);
+    let control_points = slider.path.control_points();
+    let mut path = String::new();
+    let mut last_kind = None;
+    for (i, point) in control_points.iter().enumerate() {
+        let (px, py) = (x + point.pos.x, y + point.pos.y);
+        if let Some(path_type) = point.path_type {
+            let mut explicit = last_kind != Some(path_type) || path_type.kind == SplineType::PerfectCurve;
+            // osu!stable ne sait pas lire deux derniers points dupliqués dans un segment implicite
+            if i > 1 {
+                let p1 = &control_points[i - 1].pos;
+                let p2 = &control_points[i - 2].pos;
+                if (x + p1.x) as i32 == (x + p2.x) as i32 && (y + p1.y) as i32 == (y + p2.y) as i32 {
+                    explicit = true;
+                }
+            }
+            if explicit {
+                match path_type.kind {
+                    SplineType::BSpline => match path_type.degree.filter(|&d| d > 0) {
+                        Some(degree) => path.push_str(&format!("B{}|", degree)),
+                        None => path.push_str("B|"),
+                    },
+                    SplineType::Catmull => path.push_str("C|"),
+                    SplineType::PerfectCurve => path.push_str("P|"),
+                    SplineType::Linear => path.push_str("L|"),
+                }
+                last_kind = Some(path_type);
+            } else {
+                path.push_str(&format!("{}:{}|", px, py));
+            }
+        }
+        if i != 0 {
+            path.push_str(&format!("{}:{}", px, py));
+            if i != control_points.len() - 1 {
+                path.push('|');
+            }
+        }
+    }
+
+    let length = slider.path.expected_dist().unwrap_or_default();
+    let edge_sounds: Vec<String> = slider.node_samples.iter().map(|s| hit_sound_type(s).to_string()).collect();
+    let edge_sets: Vec<String> = slider.node_samples.iter().map(|s| sample_sets(s)).collect();
+    format!(
+        "{},{},{},{},{}",
+        path,
+        slider.repeat_count + 1,
+        length,
+        edge_sounds.join("|"),
+        edge_sets.join("|"),
+    )
+}
+
+/// Ligne `[HitObjects]` complète, lisible par osu! et par `Beatmap::decode`.
+pub fn hit_object_to_string(hit_object: &HitObject) -> String {
+    let time = hit_object.start_time.round() as i32;
+    let hit_sound = hit_sound_type(&hit_object.samples);
+    let hit_sample = hit_sample_string(&hit_object.samples);
+    match &hit_object.kind {
         HitObjectKind::Circle(circle) => {
-            format!("{},{},{},{},0,0:0:0:0:0:", circle.pos.x, circle.pos.y, hit_object.start_time, 1<<0)
+            // x,y,time,type,hitSound,hitSample
+            format!(
+                "{},{},{},{},{},{}",
+                circle.pos.x,
+                circle.pos.y,
+                time,
+                type_bits(TYPE_CIRCLE, circle.new_combo, circle.combo_offset),
+                hit_sound,
+                hit_sample,
+            )
         }
         HitObjectKind::Slider(slider) => {
-            format!("{},{},{},{},0,B|0:0,1,100,0:0:0:0", slider.pos.x, slider.pos.y, hit_object.start_time, 1<<1)
+            // x,y,time,type,hitSound,curve,slides,length,edgeSounds,edgeSets,hitSample
+            format!(
+                "{},{},{},{},{},{},{}",
+                slider.pos.x,
+                slider.pos.y,
+                time,
+                type_bits(TYPE_SLIDER, slider.new_combo, slider.combo_offset),
+                hit_sound,
+                slider_params(slider),
+                hit_sample,
+            )
         }
         HitObjectKind::Spinner(spinner) => {
-            format!("256,192,{},{},0,{},0:0:0:0:", hit_object.start_time, 1<<3, hit_object.start_time + 1000.0)
+            // x,y,time,type,hitSound,endTime,hitSample
+            format!(
+                "{},{},{},{},{},{},{}",
+                spinner.pos.x,
+                spinner.pos.y,
+                time,
+                type_bits(TYPE_SPINNER, spinner.new_combo, 0),
+                hit_sound,
+                (hit_object.start_time + spinner.duration).round() as i32,
+                hit_sample,
+            )
         }
         HitObjectKind::Hold(hold) => {
-            format!("{},0,{},{},0,{},0:0:0:0:", hold.pos_x, hit_object.start_time, 1<<7, hit_object.start_time + hold.duration) 
+            // x,y,time,type,hitSound,endTime:hitSample
+            format!(
+                "{},192,{},{},{},{}:{}",
+                hold.pos_x,
+                time,
+                TYPE_HOLD,
+                hit_sound,
+                (hit_object.start_time + hold.duration).round() as i32,
+                hit_sample,
+            )
+        }
+    }
+}
+
+/// Hash FNV-1a 64 bits, stable d'une version à l'autre (contrairement au DefaultHasher).
+/// Sert à reconnaître deux snippets identiques.
+pub fn content_hash(content: &str) -> u64 {
+    content.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snippets::transform::SeededRng;
+    use rosu_map::{Beatmap, DecodeBeatmap};
+
+    /// Objets d'une map minimale contenant `lines`, avec un point rouge soft à 70%.
+    fn decode(mode: u8, lines: &[impl AsRef<str>]) -> Vec<HitObject> {
+        let lines: Vec<&str> = lines.iter().map(|l| l.as_ref()).collect();
+        let osu = format!(
+            "osu file format v14\n\n[General]\nMode: {}\n\n[Difficulty]\nCircleSize:4\n\n[TimingPoints]\n0,500,4,2,0,70,1,0\n\n[HitObjects]\n{}\n",
+            mode,
+            lines.join("\n"),
+        );
+        Beatmap::decode(osu.as_bytes()).unwrap().hit_objects
+    }
+
+    fn serialize(hit_objects: &[HitObject]) -> Vec<String> {
+        hit_objects.iter().map(hit_object_to_string).collect()
+    }
+
+    #[test]
+    fn mania_lines_round_trip_byte_identically() {
+        let lines = [
+            "448,192,1000,128,2,1500:2:0:0:70:",
+            "64,192,1250,128,10,1750:0:2:0:70:hit.wav",
+        ];
+        assert_eq!(serialize(&decode(3, &lines)), lines);
+    }
+
+    /// Tout ce que le décodeur garde d'un objet. Le chemin d'un slider est comparé
+    /// par ses control points, pas par la courbe calculée.
+    fn describe(hit_object: &HitObject) -> String {
+        let kind = match &hit_object.kind {
+            HitObjectKind::Slider(slider) => format!(
+                "Slider {:?} {} {} {} {:?} {:?} {:?}",
+                slider.pos,
+                slider.new_combo,
+                slider.combo_offset,
+                slider.repeat_count,
+                slider.path.control_points(),
+                slider.path.expected_dist(),
+                slider.node_samples,
+            ),
+            other => format!("{:?}", other),
+        };
+        format!("{} {} {:?}", hit_object.start_time, kind, hit_object.samples)
+    }
+
+    /// decode → serialize → decode redonne les mêmes objets, et la sérialisation est stable.
+    fn assert_identity(mode: u8, lines: &[String]) {
+        let decoded = decode(mode, lines);
+        let serialized = serialize(&decoded);
+        let redecoded = decode(mode, &serialized);
+        assert_eq!(decoded.len(), redecoded.len());
+        for ((before, after), line) in decoded.iter().zip(&redecoded).zip(&serialized) {
+            assert_eq!(describe(before), describe(after), "{}", line);
+        }
+        assert_eq!(serialize(&redecoded), serialized);
+    }
+
+    #[test]
+    fn multi_segment_sliders_round_trip() {
+        let lines = [
+            // Deux arcs parfaits consécutifs, toujours écrits explicitement
+            "100,100,2000,6,0,P|150:50|200:100|P|250:150|300:100,1,300,2|0,0:0|0:0,0:0:0:0:",
+            // Segments bezier implicites (point dupliqué) avec repeats
+            "100,100,3000,2,0,B|150:150|200:200|200:200|250:150|300:200,2,250,0|8|0,0:0|2:0|0:0,0:0:0:0:",
+            // Deux points identiques juste avant un nouveau segment du même type
+            "100,100,4000,2,0,B|150:150|150:150|B|200:200|250:150,1,200,0|0,0:0|0:0,0:0:0:0:",
+            // Types mélangés
+            "100,100,5000,38,0,L|200:100|B|250:150|300:100|C|350:200|400:100,1,400,0|0,0:0|0:0,0:0:0:0:",
+        ];
+        assert_identity(0, &lines.map(String::from));
+    }
+
+    fn below(rng: &mut SeededRng, n: u64) -> u64 {
+        rng.next_u64() % n
+    }
+
+    /// `hitSound` et champ hitSample tirés au hasard, le champ est absent une fois sur six.
+    /// Jamais le bit Normal : il n'est pas réécrit, c'est l'objet décodé qui dit si le sample normal est "layered".
+    fn random_sound(rng: &mut SeededRng) -> (u64, Option<String>) {
+        let hit_sound = below(rng, 8) << 1;
+        if below(rng, 6) == 0 {
+            return (hit_sound, None);
+        }
+        let normal_set = below(rng, 4);
+        let addition_set = below(rng, 4);
+        let index = if below(rng, 2) == 0 { 0 } else { below(rng, 10) };
+        let volume = if below(rng, 2) == 0 { 0 } else { 1 + below(rng, 100) };
+        let filename = match below(rng, 4) {
+            0 => format!("hit{}.wav", below(rng, 10)),
+            1 => "soft-clap2.ogg".to_string(),
+            _ => String::new(),
+        };
+        (hit_sound, Some(format!("{}:{}:{}:{}:{}", normal_set, addition_set, index, volume, filename)))
+    }
+
+    /// Notes et LN 4K : colonnes, écarts, longueurs et combo bits des LN au hasard.
+    fn random_mania_lines(seed: u64, count: usize) -> Vec<String> {
+        let mut rng = SeededRng::new(seed);
+        let mut time = 0;
+        (0..count)
+            .map(|_| {
+                time += 1 + below(&mut rng, 400);
+                let x = 64 + 128 * below(&mut rng, 4);
+                let (hit_sound, sample) = random_sound(&mut rng);
+                if below(&mut rng, 2) == 0 {
+                    let sample = sample.map(|s| format!(",{}", s)).unwrap_or_default();
+                    return format!("{},192,{},{},{}{}", x, time, TYPE_CIRCLE, hit_sound, sample);
+                }
+                let end = time + 1 + below(&mut rng, 2000);
+                let mut kind = TYPE_HOLD as u64;
+                if below(&mut rng, 3) == 0 {
+                    kind |= TYPE_NEW_COMBO as u64 | below(&mut rng, 8) << 4;
+                }
+                let sample = sample.map(|s| format!(":{}", s)).unwrap_or_default();
+                format!("{},192,{},{},{},{}{}", x, time, kind, hit_sound, end, sample)
+            })
+            .collect()
+    }
+
+    /// Cercles et spinners osu!standard avec new combo et combo offset au hasard.
+    /// Les spinners n'ont pas d'offset : il est reporté sur l'objet suivant et pas réécrit.
+    fn random_standard_lines(seed: u64, count: usize) -> Vec<String> {
+        let mut rng = SeededRng::new(seed);
+        let mut time = 0;
+        (0..count)
+            .map(|_| {
+                time += 50 + below(&mut rng, 400);
+                let new_combo = if below(&mut rng, 3) == 0 { TYPE_NEW_COMBO as u64 } else { 0 };
+                let (hit_sound, sample) = random_sound(&mut rng);
+                let sample = sample.map(|s| format!(",{}", s)).unwrap_or_default();
+                if below(&mut rng, 5) == 0 {
+                    let start = time;
+                    time += 100 + below(&mut rng, 2000);
+                    return format!("256,192,{},{},{},{}{}", start, TYPE_SPINNER as u64 | new_combo, hit_sound, time, sample);
+                }
+                let kind = TYPE_CIRCLE as u64 | new_combo | below(&mut rng, 8) << 4;
+                let (x, y) = (below(&mut rng, 513), below(&mut rng, 385));
+                format!("{},{},{},{},{}{}", x, y, time, kind, hit_sound, sample)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn generated_mania_lines_round_trip() {
+        for seed in 0..50 {
+            let lines = random_mania_lines(seed, 40);
+            assert_identity(3, &lines);
+            // Les combo bits des LN ne sont jamais réécrits
+            for (line, serialized) in lines.iter().zip(serialize(&decode(3, &lines))) {
+                let kind: i32 = line.split(',').nth(3).unwrap().parse().unwrap();
+                if kind & TYPE_HOLD != 0 {
+                    assert_eq!(serialized.split(',').nth(3), Some("128"), "{}", line);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generated_circles_and_spinners_round_trip() {
+        for seed in 0..50 {
+            assert_identity(0, &random_standard_lines(seed, 40));
         }
-        _ => String::new()
     }
-}
\ No newline at end of file
+}